@@ -0,0 +1,13 @@
+-- 记录每一次文档上传的入库任务, 便于运维人员追踪文档的处理进度和失败原因
+CREATE TABLE ingestion_jobs (
+    id UUID PRIMARY KEY,
+    uuid TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    filename TEXT NOT NULL,
+    stage TEXT NOT NULL,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX ingestion_jobs_uuid_index ON ingestion_jobs (uuid);
@@ -0,0 +1,5 @@
+-- 记录切片数量和任务结束时间, 用于查询入库任务的处理结果和耗时
+ALTER TABLE ingestion_jobs ADD COLUMN slices INTEGER;
+ALTER TABLE ingestion_jobs ADD COLUMN finished_at TIMESTAMPTZ;
+
+CREATE INDEX ingestion_jobs_kind_created_at_index ON ingestion_jobs (kind, created_at DESC);
@@ -0,0 +1,2 @@
+-- 文档删除后保留入库任务记录, 仅标记删除时间, 便于追溯
+ALTER TABLE ingestion_jobs ADD COLUMN deleted_at TIMESTAMPTZ;
@@ -0,0 +1,13 @@
+-- 同一个文档主键可以多次上传, 每次上传生成一个新版本
+ALTER TABLE ingestion_jobs ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+-- 已有的重复上传按照登记顺序补齐版本号, 否则创建唯一索引时会冲突
+UPDATE ingestion_jobs AS jobs
+SET version = numbered.version
+FROM (
+    SELECT id, row_number() OVER (PARTITION BY kind, uuid ORDER BY created_at, id) AS version
+    FROM ingestion_jobs
+) AS numbered
+WHERE jobs.id = numbered.id AND numbered.version > 1;
+
+CREATE UNIQUE INDEX ingestion_jobs_kind_uuid_version_index ON ingestion_jobs (kind, uuid, version);
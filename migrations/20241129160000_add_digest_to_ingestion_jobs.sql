@@ -0,0 +1,5 @@
+-- 记录文档内容的哈希值, 内容相同的文档直接复用已有的向量
+ALTER TABLE ingestion_jobs ADD COLUMN digest TEXT;
+ALTER TABLE ingestion_jobs ADD COLUMN duplicate_of UUID;
+
+CREATE INDEX ingestion_jobs_kind_digest_index ON ingestion_jobs (kind, digest);
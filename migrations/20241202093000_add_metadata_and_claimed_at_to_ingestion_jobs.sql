@@ -0,0 +1,7 @@
+-- 保存入库所需的文档信息, 服务重启后可以根据任务记录继续入库
+ALTER TABLE ingestion_jobs ADD COLUMN metadata JSONB;
+-- 工作任务领取入库任务的时间, 超过租约时长没有更新的任务可以被重新领取
+ALTER TABLE ingestion_jobs ADD COLUMN claimed_at TIMESTAMPTZ;
+
+CREATE INDEX ingestion_jobs_pending_index ON ingestion_jobs (created_at)
+    WHERE error IS NULL AND deleted_at IS NULL AND stage <> 'indexed';
@@ -0,0 +1,2 @@
+-- 记录入库失败的类型, 例如代理服务超时、连接失败、响应格式无效等
+ALTER TABLE ingestion_jobs ADD COLUMN error_kind TEXT;
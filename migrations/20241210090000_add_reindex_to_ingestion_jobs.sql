@@ -0,0 +1,2 @@
+-- 标记通过重新入库退回的任务, 重新入库需要按照当前配置重新切片和嵌入, 不复用内容相同的已入库文档
+ALTER TABLE ingestion_jobs ADD COLUMN reindex BOOLEAN NOT NULL DEFAULT FALSE;
@@ -1,9 +1,11 @@
 use std::fmt;
 
 use actix_web::http::StatusCode;
-use actix_web::ResponseError;
+use actix_web::{HttpResponse, ResponseError};
 
 use crate::blunder::errchain;
+use crate::blunder::proxy::ProxyError;
+use crate::dto::response::document::generally::DocumentResponse;
 
 #[derive(thiserror::Error)]
 pub enum ParseError {
@@ -15,6 +17,93 @@ pub enum ParseError {
 
     #[error("扩展名无效")]
     InvalidExtension,
+
+    #[error("文件读取失败: {0}")]
+    UnreadableFile(String),
+
+    #[error("文件类型与扩展名不符, 扩展名为{0}, 文件内容为{1}")]
+    MismatchedType(String, String),
+
+    #[error("文件大小超过限制, {0}文件最大为{1}字节")]
+    FileTooLarge(String, usize),
+
+    #[error("文件解压后大小超过限制, 最大为{0}字节")]
+    DecompressedTooLarge(u64),
+
+    #[error("文档页数超过限制, 最多为{0}页")]
+    TooManyPages(u32),
+
+    #[error("无法统计pdf文档的页数")]
+    UncountablePages,
+
+    #[error("工作表数量超过限制, 最多为{0}个")]
+    TooManySheets(usize),
+
+    #[error("批量上传的文档为空")]
+    EmptyBatch,
+
+    #[error("批量上传的文档数量超过限制, 最多为{0}个")]
+    TooManyFiles(usize),
+
+    #[error("文档清单格式无效: {0}")]
+    InvalidManifest(String),
+
+    #[error("文档数量与清单数量不一致, 文档{0}个, 清单{1}条")]
+    ManifestMismatch(usize, usize),
+
+    #[error("第{0}个文档无效: {1}")]
+    InvalidBatchItem(usize, Box<ParseError>),
+
+    #[error("文档主键重复: {0}")]
+    DuplicateUuid(String),
+
+    #[error("导入目录无效: {0}")]
+    InvalidDirectory(String),
+
+    #[error("导入目录不在允许的范围内: {0}")]
+    ForbiddenDirectory(String),
+
+    #[error("文档清单不存在: {0}")]
+    MissingManifest(String),
+
+    #[error("导入文件不在导入目录中: {0}")]
+    ForbiddenFile(String),
+
+    #[error("检索问题为空")]
+    EmptyQuestion,
+
+    #[error("返回数量无效, 取值范围为1到{0}")]
+    InvalidLimit(u64),
+
+    #[error("日期格式无效: {0}")]
+    InvalidDate(String),
+
+    #[error("文档主键无效: {0}")]
+    InvalidUuid(String),
+
+    #[error("文档标题为空")]
+    EmptyTitle,
+
+    #[error("文档标题过长, 最多为{0}个字符")]
+    TitleTooLong(usize),
+
+    #[error("发布机构为空")]
+    EmptyAuthority,
+
+    #[error("应用范围无效: {0}")]
+    InvalidArea(String),
+
+    #[error("文档来源无效: {0}")]
+    InvalidSource(String),
+
+    #[error("起始日期晚于截止日期")]
+    InvalidDateRange,
+
+    #[error("分页参数无效, 页码取值范围为1到{0}, 每页数量取值范围为1到{1}")]
+    InvalidPagination(u64, u64),
+
+    #[error("任务状态无效: {0}")]
+    InvalidStatus(String),
 }
 
 impl fmt::Debug for ParseError {
@@ -28,6 +117,18 @@ pub enum DocumentError {
     #[error("文档解析错误: {0}")]
     ValidationError(#[from] ParseError),
 
+    #[error("资源不存在: {0}")]
+    NotFoundError(String),
+
+    #[error("资源冲突: {0}")]
+    ConflictError(String),
+
+    #[error("服务繁忙: {0}")]
+    UnavailableError(String),
+
+    #[error(transparent)]
+    ProxyError(#[from] ProxyError),
+
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -38,6 +139,42 @@ impl fmt::Debug for DocumentError {
     }
 }
 
+impl DocumentError {
+    // 代理服务的错误可能直接返回, 也可能经过anyhow添加上下文后返回
+    pub fn proxy_error(&self) -> Option<&ProxyError> {
+        match self {
+            DocumentError::ProxyError(error) => Some(error),
+            DocumentError::UnexpectedError(error) => error.downcast_ref::<ProxyError>(),
+            _ => None,
+        }
+    }
+
+    // 返回给前端的错误信息: 熔断时告知前端重试时间, 其他代理服务错误只返回服务名称,
+    // 服务端错误的细节只记录在日志中
+    pub fn message(&self) -> String {
+        match (self.proxy_error(), self) {
+            (Some(error @ ProxyError::CircuitOpen { .. }), _) => error.to_string(),
+            (Some(error), _) => format!("依赖服务{}调用失败", error.endpoint()),
+            (None, DocumentError::UnexpectedError(_)) => "服务内部错误".to_string(),
+            (None, _) => self.to_string(),
+        }
+    }
+
+    // 记录在入库任务中的失败类型
+    pub fn kind(&self) -> &'static str {
+        if let Some(error) = self.proxy_error() {
+            return error.kind();
+        }
+        match self {
+            DocumentError::ValidationError(_) => "validation",
+            DocumentError::NotFoundError(_) => "not_found",
+            DocumentError::ConflictError(_) => "conflict",
+            DocumentError::UnavailableError(_) => "unavailable",
+            _ => "unexpected",
+        }
+    }
+}
+
 // impl fmt::Debug for DocumentError {
 //     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 //         match self {
@@ -57,25 +194,44 @@ impl fmt::Debug for DocumentError {
 impl ResponseError for DocumentError {
     // 自定义状态码
     fn status_code(&self) -> StatusCode {
+        if let Some(error) = self.proxy_error() {
+            return error.status_code();
+        }
         match self {
             DocumentError::ValidationError(_) => StatusCode::BAD_REQUEST,
-            DocumentError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            DocumentError::NotFoundError(_) => StatusCode::NOT_FOUND,
+            DocumentError::ConflictError(_) => StatusCode::CONFLICT,
+            DocumentError::UnavailableError(_) => StatusCode::SERVICE_UNAVAILABLE,
+            // 代理服务的错误已经在上面按照代理错误返回状态码
+            DocumentError::ProxyError(_) | DocumentError::UnexpectedError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
         }
     }
 
-    // 自定义错误响应
-    // fn error_response(&self) -> HttpResponse<BoxBody> {
-    //     match self {
-    //         // 当遇到客户度错误时, 不想直接给前端返回400状态码, 而是想返回200状态码以及额外的信息, 例如:
-    //         // {"code": 400, "error": "xxx"}
-    //         DocumentError::ValidationError(error) => {
-    //             HttpResponse::Ok().json(DocumentResponse::bad_request(error.to_string()))
-    //         }
-    //         // 当遇到服务端错误时, 不想直接给前端返回500状态码, 而是想返回200状态码以及额外的信息, 例如:
-    //         // {"code": 500, "error": "xxx"}
-    //         DocumentError::UnexpectedError(error) => {
-    //             HttpResponse::Ok().json(DocumentResponse::internal_server_error(error.to_string()))
-    //         }
-    //     }
-    // }
+    // 自定义错误响应, 统一使用{code, message, data, request_id}格式返回给前端
+    fn error_response(&self) -> HttpResponse {
+        DocumentResponse::failure(self.status_code(), self.message())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn mask_unexpected_error_message() {
+        let error = DocumentError::from(
+            anyhow!("Permission denied").context("Failed to save document to \"/data/cache\""),
+        );
+        assert_eq!(error.message(), "服务内部错误");
+
+        let error = DocumentError::from(ParseError::TooManyFiles(8));
+        assert_eq!(
+            error.message(),
+            "文档解析错误: 批量上传的文档数量超过限制, 最多为8个"
+        );
+    }
 }
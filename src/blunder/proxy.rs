@@ -0,0 +1,181 @@
+use std::fmt;
+
+use actix_web::http::StatusCode;
+
+use crate::blunder::errchain;
+use crate::configuration::itools::RetrySettings;
+
+// 调用代理服务失败的具体原因, 重试、熔断以及任务记录都根据失败类型作出不同处理,
+// 耗时均为单次请求的毫秒数, 响应内容只保留开头部分
+#[derive(thiserror::Error)]
+pub enum ProxyError {
+    #[error("代理服务{endpoint}请求超时, 耗时{elapsed}毫秒")]
+    Timeout {
+        endpoint: &'static str,
+        elapsed: u64,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("代理服务{endpoint}连接失败, 耗时{elapsed}毫秒")]
+    Connect {
+        endpoint: &'static str,
+        elapsed: u64,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("代理服务{endpoint}请求失败, 耗时{elapsed}毫秒")]
+    Request {
+        endpoint: &'static str,
+        elapsed: u64,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("代理服务{endpoint}拒绝请求, 状态码: {status}, 耗时{elapsed}毫秒, 响应: {body}")]
+    ClientStatus {
+        endpoint: &'static str,
+        status: u16,
+        body: String,
+        elapsed: u64,
+    },
+
+    #[error("代理服务{endpoint}处理失败, 状态码: {status}, 耗时{elapsed}毫秒, 响应: {body}")]
+    ServerStatus {
+        endpoint: &'static str,
+        status: u16,
+        body: String,
+        elapsed: u64,
+    },
+
+    #[error("代理服务{endpoint}响应格式无效, 耗时{elapsed}毫秒, 响应: {body}")]
+    Decode {
+        endpoint: &'static str,
+        body: String,
+        elapsed: u64,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("代理服务{endpoint}已熔断, 请在{retry_after}秒后重试")]
+    CircuitOpen {
+        endpoint: &'static str,
+        retry_after: u64,
+    },
+}
+
+impl fmt::Debug for ProxyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        errchain::errorchain(self, f)
+    }
+}
+
+// 响应内容最多保留的字符数
+const BODY_SNIPPET_CHARS: usize = 256;
+
+pub fn body_snippet(body: &str) -> String {
+    match body.char_indices().nth(BODY_SNIPPET_CHARS) {
+        Some((index, _)) => format!("{}...", &body[..index]),
+        None => body.to_string(),
+    }
+}
+
+impl ProxyError {
+    pub fn endpoint(&self) -> &'static str {
+        match self {
+            ProxyError::Timeout { endpoint, .. }
+            | ProxyError::Connect { endpoint, .. }
+            | ProxyError::Request { endpoint, .. }
+            | ProxyError::ClientStatus { endpoint, .. }
+            | ProxyError::ServerStatus { endpoint, .. }
+            | ProxyError::Decode { endpoint, .. }
+            | ProxyError::CircuitOpen { endpoint, .. } => endpoint,
+        }
+    }
+
+    // 记录在入库任务中的失败类型
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ProxyError::Timeout { .. } => "proxy_timeout",
+            ProxyError::Connect { .. } => "proxy_connect",
+            ProxyError::Request { .. } => "proxy_request",
+            ProxyError::ClientStatus { .. } => "proxy_client_status",
+            ProxyError::ServerStatus { .. } => "proxy_server_status",
+            ProxyError::Decode { .. } => "proxy_decode",
+            ProxyError::CircuitOpen { .. } => "proxy_circuit_open",
+        }
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ProxyError::ClientStatus { status, .. } | ProxyError::ServerStatus { status, .. } => {
+                Some(*status)
+            }
+            _ => None,
+        }
+    }
+
+    // 超时、连接失败以及配置为可重试的状态码才会重试, 熔断和响应格式错误重试也无济于事
+    pub fn is_retryable(&self, retry: &RetrySettings) -> bool {
+        match self {
+            ProxyError::Timeout { .. } | ProxyError::Connect { .. } => true,
+            ProxyError::ClientStatus { status, .. } | ProxyError::ServerStatus { status, .. } => {
+                retry.retryable_statuses.contains(status)
+            }
+            _ => false,
+        }
+    }
+
+    // 代理服务自身的故障才计入熔断器的连续失败次数, 请求参数错误不代表服务不可用
+    pub fn is_outage(&self) -> bool {
+        matches!(
+            self,
+            ProxyError::Timeout { .. }
+                | ProxyError::Connect { .. }
+                | ProxyError::Request { .. }
+                | ProxyError::ServerStatus { .. }
+        )
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ProxyError::CircuitOpen { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ProxyError::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::BAD_GATEWAY,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::ResponseError;
+    use anyhow::Context;
+
+    use super::*;
+    use crate::blunder::document::DocumentError;
+
+    #[test]
+    fn map_proxy_error_through_context() {
+        let result: Result<(), ProxyError> = Err(ProxyError::CircuitOpen {
+            endpoint: "embedding",
+            retry_after: 10,
+        });
+        let error = DocumentError::from(
+            result
+                .context("Failed to run document embedding")
+                .unwrap_err(),
+        );
+        assert_eq!(error.kind(), "proxy_circuit_open");
+        assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn truncate_body_snippet() {
+        assert_eq!(body_snippet("服务异常"), "服务异常");
+        let body = "错".repeat(BODY_SNIPPET_CHARS + 10);
+        let snippet = body_snippet(&body);
+        assert_eq!(snippet.chars().count(), BODY_SNIPPET_CHARS + 3);
+        assert!(snippet.ends_with("..."));
+    }
+}
@@ -0,0 +1,188 @@
+pub mod collection;
+pub mod document;
+pub mod setting;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::configuration::common::CommonSettings;
+use crate::configuration::setting::Settings;
+use crate::database::ingestion::JobKind;
+use crate::dto::request::document::generally::JobsRequest;
+use crate::startup::{build_context, migrate_database, Application};
+use crate::worker::ingestion::IngestionContext;
+
+/// 审计知识库服务, 不指定子命令时启动HTTP服务
+#[derive(Parser)]
+#[command(name = "iaudit", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// 启动HTTP服务和入库工作任务
+    Serve,
+    /// 执行数据库迁移
+    Migrate,
+    /// 校验配置文件, 并检查数据库和向量库能否连接
+    Validate {
+        /// 只校验配置文件, 不连接数据库和向量库
+        #[arg(long)]
+        offline: bool,
+    },
+    /// 管理向量库集合
+    #[command(subcommand)]
+    Collection(CollectionCommand),
+    /// 分页查询入库任务
+    Jobs {
+        kind: DocumentKind,
+        /// 任务状态: processing、finished或failed
+        #[arg(long)]
+        status: Option<String>,
+        #[arg(long)]
+        page: Option<u64>,
+        #[arg(long)]
+        size: Option<u64>,
+    },
+    /// 从已保存的文档重新入库最新版本, 完成后输出任务状态
+    Reindex { kind: DocumentKind, uuid: String },
+    /// 删除文档的向量和缓存文件, 并将任务记录标记为删除
+    Purge { kind: DocumentKind, uuid: String },
+}
+
+#[derive(Subcommand)]
+pub enum CollectionCommand {
+    /// 创建集合, 已经存在的集合保持不变
+    Create {
+        /// 未指定时处理全部集合
+        kind: Option<DocumentKind>,
+        /// 向量维度, 未指定时调用嵌入服务获取
+        #[arg(long)]
+        size: Option<u64>,
+    },
+    /// 删除后重新创建集合, 集合中已有的向量全部丢失
+    Recreate {
+        /// 未指定时处理全部集合
+        kind: Option<DocumentKind>,
+        /// 向量维度, 未指定时调用嵌入服务获取
+        #[arg(long)]
+        size: Option<u64>,
+        /// 确认删除集合中已有的向量
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DocumentKind {
+    Thinktank,
+    Guideline,
+}
+
+impl DocumentKind {
+    pub fn job_kind(self) -> JobKind {
+        match self {
+            DocumentKind::Thinktank => JobKind::Thinktank,
+            DocumentKind::Guideline => JobKind::Guideline,
+        }
+    }
+
+    pub fn collection(self, common: &CommonSettings) -> &str {
+        match self {
+            DocumentKind::Thinktank => &common.thinktank_collection,
+            DocumentKind::Guideline => &common.guideline_collection,
+        }
+    }
+
+    pub fn cache(self, common: &CommonSettings) -> &str {
+        match self {
+            DocumentKind::Thinktank => &common.thinktank_cache,
+            DocumentKind::Guideline => &common.guideline_cache,
+        }
+    }
+}
+
+// 管理命令与HTTP接口共用服务层, 命令的结果输出到标准输出, 失败时以非零状态退出
+pub async fn execute(command: Command, configuration: Settings) -> Result<(), anyhow::Error> {
+    match command {
+        Command::Serve => {
+            let application = Application::build(configuration).await?;
+            application.run_until_stopped().await?;
+        }
+        Command::Migrate => {
+            let pgpool = configuration.postgres.get_postgres_connection_pool();
+            migrate_database(&pgpool).await?;
+            println!("数据库迁移完成");
+        }
+        Command::Validate { offline } => setting::validate(&configuration, offline).await?,
+        Command::Collection(command) => {
+            collection::execute(command, &context(configuration)).await?
+        }
+        Command::Jobs {
+            kind,
+            status,
+            page,
+            size,
+        } => {
+            let request = JobsRequest { page, size, status };
+            document::jobs(kind, request, &context(configuration)).await?
+        }
+        Command::Reindex { kind, uuid } => {
+            let lease = configuration.worker.lease_timeout;
+            document::reindex(kind, uuid, lease, &context(configuration)).await?
+        }
+        Command::Purge { kind, uuid } => {
+            document::purge(kind, uuid, &context(configuration)).await?
+        }
+    }
+    Ok(())
+}
+
+fn context(configuration: Settings) -> IngestionContext {
+    build_context(
+        &configuration.postgres,
+        &configuration.qdrant,
+        &configuration.client,
+        configuration.itools,
+        configuration.common,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn parse_command_line() {
+        Cli::command().debug_assert();
+
+        assert!(Cli::try_parse_from(["iaudit"]).unwrap().command.is_none());
+        let cli = Cli::try_parse_from([
+            "iaudit",
+            "reindex",
+            "guideline",
+            "8d6e5b7a-1c2f-4e3a-9b4d-5f6a7b8c9d0e",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Reindex {
+                kind: DocumentKind::Guideline,
+                ..
+            })
+        ));
+        let cli = Cli::try_parse_from(["iaudit", "collection", "recreate", "--size", "1024"]);
+        assert!(matches!(
+            cli.unwrap().command,
+            Some(Command::Collection(CollectionCommand::Recreate {
+                kind: None,
+                size: Some(1024),
+                yes: false,
+            }))
+        ));
+        assert!(Cli::try_parse_from(["iaudit", "purge", "unknown", "uuid"]).is_err());
+    }
+}
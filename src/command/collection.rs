@@ -0,0 +1,53 @@
+use anyhow::{ensure, Context, Error};
+use clap::ValueEnum;
+use serde_json::json;
+
+use crate::command::{CollectionCommand, DocumentKind};
+use crate::helper::{proxy, vector};
+use crate::worker::ingestion::IngestionContext;
+
+pub async fn execute(command: CollectionCommand, context: &IngestionContext) -> Result<(), Error> {
+    let (kind, size, recreate) = match command {
+        CollectionCommand::Create { kind, size } => (kind, size, false),
+        CollectionCommand::Recreate { kind, size, yes } => {
+            ensure!(
+                yes,
+                "重新创建集合会删除集合中已有的全部向量, 确认后请加上--yes参数"
+            );
+            (kind, size, true)
+        }
+    };
+    let size = match size {
+        Some(size) => size,
+        None => embedding_size(context).await?,
+    };
+    let kinds = match kind {
+        Some(kind) => vec![kind],
+        None => DocumentKind::value_variants().to_vec(),
+    };
+
+    let qdrant = context.qdrant.lock().await;
+    for kind in kinds {
+        let collection = kind.collection(&context.common);
+        if recreate {
+            vector::recreate_collection(&qdrant, collection, size).await?;
+            println!("集合{}已重新创建, 向量维度为{}", collection, size);
+        } else {
+            vector::ensure_collection(&qdrant, collection, size).await?;
+            println!("集合{}已就绪", collection);
+        }
+    }
+    Ok(())
+}
+
+// 向量维度由嵌入模型决定, 嵌入一段探测文本获取
+async fn embedding_size(context: &IngestionContext) -> Result<u64, Error> {
+    let vector = proxy::document_embedding(
+        &context.client,
+        &context.itools.embedding_proxy(),
+        json!({"content": "向量维度探测"}),
+    )
+    .await
+    .context("Failed to probe embedding size")?;
+    Ok(vector.len() as u64)
+}
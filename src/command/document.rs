@@ -0,0 +1,67 @@
+use anyhow::{ensure, Error};
+use serde::Serialize;
+
+use crate::command::DocumentKind;
+use crate::dto::request::document::generally::JobsRequest;
+use crate::dto::response::document::generally::{DeleteResponse, JobResponse, JobsResponse};
+use crate::dto::transformer::document::generally as transformer;
+use crate::service::document::generally;
+use crate::worker::ingestion::{self, IngestionContext};
+
+pub async fn jobs(
+    kind: DocumentKind,
+    request: JobsRequest,
+    context: &IngestionContext,
+) -> Result<(), Error> {
+    let domain = request.try_into()?;
+    let response = generally::jobs(domain, &context.pgpool, kind.job_kind()).await?;
+    print(&JobsResponse::from(response))
+}
+
+// 在当前进程中直接处理任务, 不经过服务的入库队列, 处理完成后输出任务的最终状态
+pub async fn reindex(
+    kind: DocumentKind,
+    uuid: String,
+    lease: u64,
+    context: &IngestionContext,
+) -> Result<(), Error> {
+    let uuid = transformer::uuid(uuid)?.to_string();
+    let job = generally::reindex(&context.pgpool, kind.job_kind(), &uuid).await?;
+    ingestion::process(job.id, lease, context).await;
+
+    let response =
+        JobResponse::from(generally::status(&context.pgpool, kind.job_kind(), &uuid).await?);
+    print(&response)?;
+    ensure!(response.error.is_none(), "文档{}重新入库失败", uuid);
+    Ok(())
+}
+
+pub async fn purge(
+    kind: DocumentKind,
+    uuid: String,
+    context: &IngestionContext,
+) -> Result<(), Error> {
+    let uuid = transformer::uuid(uuid)?.to_string();
+    let response = generally::delete(
+        &context.pgpool,
+        &context.qdrant,
+        kind.job_kind(),
+        kind.collection(&context.common),
+        kind.cache(&context.common),
+        &uuid,
+    )
+    .await?;
+    let complete = response.is_complete();
+    print(&DeleteResponse::from(response))?;
+    ensure!(
+        complete,
+        "文档{}部分删除失败, 可以重新执行以重试失败的步骤",
+        uuid
+    );
+    Ok(())
+}
+
+fn print<T: Serialize>(value: &T) -> Result<(), Error> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use anyhow::{ensure, Error};
+
+use crate::configuration::setting::Settings;
+
+// 依次检查配置项之间的约束以及数据库和向量库的连接, 全部检查完成后汇总输出
+pub async fn validate(configuration: &Settings, offline: bool) -> Result<(), Error> {
+    let mut problems = configuration.problems();
+    if !offline {
+        let pgpool = configuration.postgres.get_postgres_connection_pool();
+        if let Err(error) = sqlx::query("SELECT 1").execute(&pgpool).await {
+            problems.push(format!("postgres: 无法连接数据库: {}", error));
+        }
+        let qdrant = configuration.qdrant.get_qdrant_client()?;
+        if let Err(error) = qdrant.health_check().await {
+            problems.push(format!("qdrant: 无法连接向量库: {}", error));
+        }
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    ensure!(
+        problems.is_empty(),
+        "配置校验未通过, 共{}项问题",
+        problems.len()
+    );
+    println!("配置校验通过");
+    Ok(())
+}
@@ -2,6 +2,10 @@ pub mod application;
 pub mod client;
 pub mod common;
 pub mod itools;
+pub mod limits;
 pub mod postgres;
 pub mod qdrant;
 pub mod setting;
+pub mod splitting;
+pub mod vocabulary;
+pub mod worker;
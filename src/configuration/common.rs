@@ -1,7 +1,22 @@
 use serde::Deserialize;
 
+use crate::configuration::limits::LimitSettings;
+use crate::configuration::splitting::SplittingSettings;
+use crate::configuration::vocabulary::VocabularySettings;
+
 #[derive(Deserialize)]
 pub struct CommonSettings {
     pub thinktank_cache: String,
     pub guideline_cache: String,
+    pub thinktank_collection: String,
+    pub guideline_collection: String,
+    pub retain_versions: bool,
+    pub thinktank_splitting: SplittingSettings,
+    pub guideline_splitting: SplittingSettings,
+    pub limits: LimitSettings,
+    #[serde(default)]
+    pub vocabulary: VocabularySettings,
+    // 允许批量导入的服务器目录, 未配置时不允许导入
+    #[serde(default)]
+    pub import_roots: Vec<String>,
 }
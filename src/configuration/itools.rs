@@ -1,9 +1,12 @@
+use std::collections::HashMap;
+
 use serde::Deserialize;
 
 #[derive(Deserialize)]
 pub struct ItoolsSettings {
     pub proxy_route: String,
     pub word_to_pdf: String,
+    pub ppt_to_pdf: String,
     pub pdf_to_html: String,
     pub docx_reader: String,
     pub pdfx_reader: String,
@@ -11,38 +14,97 @@ pub struct ItoolsSettings {
     pub splitting: String,
     pub reranking: String,
     pub embedding: String,
+    pub retry: RetrySettings,
+    pub circuit: CircuitSettings,
+    // 按服务名称覆盖默认的重试策略, 例如: embedding、pdfx_reader
+    #[serde(default)]
+    pub retries: HashMap<String, RetrySettings>,
+    // 按扩展名选择文本提取方式, 例如: docx: native, 未配置的扩展名使用itools服务
+    #[serde(default)]
+    pub extractors: HashMap<String, ExtractorKind>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtractorKind {
+    #[default]
+    Itools,
+    Native,
+}
+
+// 调用代理服务失败时的重试策略, 退避时间按照指数增长且不超过上限, 单位为毫秒
+#[derive(Clone, Debug, Deserialize)]
+pub struct RetrySettings {
+    pub max_attempts: u32,
+    pub initial_backoff: u64,
+    pub max_backoff: u64,
+    pub jitter: bool,
+    pub retryable_statuses: Vec<u16>,
+}
+
+// 代理服务连续失败达到阈值后熔断, 熔断持续open_timeout秒后放行一个探测请求
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct CircuitSettings {
+    pub failure_threshold: u32,
+    pub open_timeout: u64,
+}
+
+// 代理服务的名称、地址以及该服务使用的重试和熔断策略
+#[derive(Clone, Debug)]
+pub struct ProxyEndpoint {
+    pub name: &'static str,
+    pub url: String,
+    pub retry: RetrySettings,
+    pub circuit: CircuitSettings,
 }
 
 impl ItoolsSettings {
-    pub fn word_to_pdf_proxy(&self) -> String {
-        format!("{}{}", self.proxy_route, self.word_to_pdf)
+    pub fn extractor(&self, extension: &str) -> ExtractorKind {
+        self.extractors.get(extension).copied().unwrap_or_default()
+    }
+
+    fn endpoint(&self, name: &'static str, path: &str) -> ProxyEndpoint {
+        ProxyEndpoint {
+            name,
+            url: format!("{}{}", self.proxy_route, path),
+            retry: self.retries.get(name).unwrap_or(&self.retry).clone(),
+            circuit: self.circuit,
+        }
+    }
+
+    pub fn word_to_pdf_proxy(&self) -> ProxyEndpoint {
+        self.endpoint("word_to_pdf", &self.word_to_pdf)
+    }
+
+    pub fn ppt_to_pdf_proxy(&self) -> ProxyEndpoint {
+        self.endpoint("ppt_to_pdf", &self.ppt_to_pdf)
     }
 
-    pub fn pdf_to_html_proxy(&self) -> String {
-        format!("{}{}", self.proxy_route, self.pdf_to_html)
+    pub fn pdf_to_html_proxy(&self) -> ProxyEndpoint {
+        self.endpoint("pdf_to_html", &self.pdf_to_html)
     }
 
-    pub fn docx_reader_proxy(&self) -> String {
-        format!("{}{}", self.proxy_route, self.docx_reader)
+    pub fn docx_reader_proxy(&self) -> ProxyEndpoint {
+        self.endpoint("docx_reader", &self.docx_reader)
     }
 
-    pub fn pdfx_reader_proxy(&self) -> String {
-        format!("{}{}", self.proxy_route, self.pdfx_reader)
+    pub fn pdfx_reader_proxy(&self) -> ProxyEndpoint {
+        self.endpoint("pdfx_reader", &self.pdfx_reader)
     }
 
-    pub fn xlsx_reader_proxy(&self) -> String {
-        format!("{}{}", self.proxy_route, self.xlsx_reader)
+    pub fn xlsx_reader_proxy(&self) -> ProxyEndpoint {
+        self.endpoint("xlsx_reader", &self.xlsx_reader)
     }
 
-    pub fn embedding_proxy(&self) -> String {
-        format!("{}{}", self.proxy_route, self.embedding)
+    pub fn embedding_proxy(&self) -> ProxyEndpoint {
+        self.endpoint("embedding", &self.embedding)
     }
 
-    pub fn reranking_proxy(&self) -> String {
-        format!("{}{}", self.proxy_route, self.reranking)
+    pub fn reranking_proxy(&self) -> ProxyEndpoint {
+        self.endpoint("reranking", &self.reranking)
     }
 
-    pub fn splitting_proxy(&self) -> String {
-        format!("{}{}", self.proxy_route, self.splitting)
+    pub fn splitting_proxy(&self) -> ProxyEndpoint {
+        self.endpoint("splitting", &self.splitting)
     }
 }
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+// 上传文档的限制, 大小单位为字节, 按扩展名配置的大小限制优先于默认限制, 例如: pdf: 104857600,
+// 批量上传时限制单次请求的文档数量和请求总大小
+#[derive(Clone, Debug, Deserialize)]
+pub struct LimitSettings {
+    pub max_file_size: usize,
+    #[serde(default)]
+    pub file_sizes: HashMap<String, usize>,
+    pub max_decompressed_size: u64,
+    pub max_pages: u32,
+    pub max_sheets: usize,
+    pub max_batch_files: usize,
+    pub max_batch_size: usize,
+}
+
+impl LimitSettings {
+    pub fn file_size(&self, extension: &str) -> usize {
+        self.file_sizes
+            .get(extension)
+            .copied()
+            .unwrap_or(self.max_file_size)
+    }
+
+    // 整个上传请求的大小上限, 在最大的文件限制之外为其他表单字段预留空间
+    pub fn request_size(&self) -> usize {
+        self.file_sizes
+            .values()
+            .copied()
+            .fold(self.max_file_size, usize::max)
+            + FORM_FIELDS_BYTES
+    }
+
+    pub fn batch_request_size(&self) -> usize {
+        self.max_batch_size + FORM_FIELDS_BYTES
+    }
+
+    // 各项上限都必须为正数, 批量上传的总大小至少要能容纳单个最大的文件
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.max_file_size == 0 || self.file_sizes.values().any(|size| *size == 0) {
+            problems.push("文件大小上限必须大于0".to_string());
+        }
+        if self.max_pages == 0 || self.max_sheets == 0 {
+            problems.push("页数和工作表数量上限必须大于0".to_string());
+        }
+        if self.max_batch_files == 0 {
+            problems.push("批量上传的文档数量上限必须大于0".to_string());
+        }
+        if self.max_batch_size < self.max_file_size {
+            problems.push(format!(
+                "批量上传的总大小上限{}小于单个文件的大小上限{}",
+                self.max_batch_size, self.max_file_size
+            ));
+        }
+        problems
+    }
+}
+
+const FORM_FIELDS_BYTES: usize = 64 << 10;
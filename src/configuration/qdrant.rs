@@ -8,6 +8,7 @@ pub struct QdrantSettings {
 }
 
 impl QdrantSettings {
+    #[allow(clippy::result_large_err)]
     pub fn get_qdrant_client(&self) -> Result<Qdrant, QdrantError> {
         Qdrant::from_url(format!("http://{}:{}", self.host, self.port).as_str()).build()
     }
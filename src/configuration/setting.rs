@@ -1,4 +1,5 @@
 use std::env;
+use std::path::Path;
 
 use config::{Config, ConfigError};
 use serde::Deserialize;
@@ -9,6 +10,7 @@ use crate::configuration::common::CommonSettings;
 use crate::configuration::itools::ItoolsSettings;
 use crate::configuration::postgres::PostgresSettings;
 use crate::configuration::qdrant::QdrantSettings;
+use crate::configuration::worker::WorkerSettings;
 
 enum Environment {
     Local,
@@ -47,6 +49,50 @@ pub struct Settings {
     pub itools: ItoolsSettings,
     pub common: CommonSettings,
     pub client: ClientSettings,
+    pub worker: WorkerSettings,
+}
+
+impl Settings {
+    // 检查配置项之间的约束, 返回全部不满足的项目, 每一项都标明所在的配置段
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let splittings = [
+            (
+                "common.thinktank_splitting",
+                &self.common.thinktank_splitting,
+            ),
+            (
+                "common.guideline_splitting",
+                &self.common.guideline_splitting,
+            ),
+        ];
+        for (section, splitting) in splittings {
+            for problem in splitting.problems() {
+                problems.push(format!("{}: {}", section, problem));
+            }
+        }
+        for problem in self.common.limits.problems() {
+            problems.push(format!("common.limits: {}", problem));
+        }
+        for root in &self.common.import_roots {
+            if !Path::new(root).is_dir() {
+                problems.push(format!("common.import_roots: 导入目录{}不存在", root));
+            }
+        }
+        if self.worker.concurrency == 0 || self.worker.queue_depth == 0 {
+            problems.push("worker: 并发数量和排队上限必须大于0".to_string());
+        }
+        if self.common.limits.max_batch_files > self.worker.queue_depth {
+            problems.push(format!(
+                "common.limits: 批量上传的文档数量上限{}超过入库队列的排队上限{}",
+                self.common.limits.max_batch_files, self.worker.queue_depth
+            ));
+        }
+        if self.worker.lease_timeout == 0 {
+            problems.push("worker: 任务租约时间必须大于0".to_string());
+        }
+        problems
+    }
 }
 
 pub fn get_configuration() -> Result<Settings, ConfigError> {
@@ -0,0 +1,44 @@
+use serde::Deserialize;
+
+// 切片方式: itools使用远程切片服务, 其余为本地实现
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplittingStrategy {
+    Itools,
+    Fixed,
+    Sentence,
+    Section,
+}
+
+// 切片长度和重叠长度均按字符计算, 表格文档按行切片, 每个切片包含rows_per_slice行
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct SplittingSettings {
+    pub strategy: SplittingStrategy,
+    pub chunk_size: usize,
+    pub chunk_overlap: usize,
+    #[serde(default = "default_rows_per_slice")]
+    pub rows_per_slice: usize,
+}
+
+fn default_rows_per_slice() -> usize {
+    1
+}
+
+impl SplittingSettings {
+    // 重叠部分不能覆盖整个切片, 否则切片位置无法向前推进
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.chunk_size == 0 {
+            problems.push("切片长度必须大于0".to_string());
+        } else if self.chunk_overlap >= self.chunk_size {
+            problems.push(format!(
+                "重叠长度{}必须小于切片长度{}",
+                self.chunk_overlap, self.chunk_size
+            ));
+        }
+        if self.rows_per_slice == 0 {
+            problems.push("每个切片包含的行数必须大于0".to_string());
+        }
+        problems
+    }
+}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+// 上传文档时应用范围和文档来源的可选取值, 未配置时不限制取值
+#[derive(Default, Deserialize)]
+pub struct VocabularySettings {
+    #[serde(default)]
+    pub areas: Vec<String>,
+    #[serde(default)]
+    pub sources: Vec<String>,
+}
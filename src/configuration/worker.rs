@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+// 入库任务的并发数量、排队上限、停机时等待剩余任务完成的秒数以及领取任务的租约秒数
+#[derive(Deserialize)]
+pub struct WorkerSettings {
+    pub concurrency: usize,
+    pub queue_depth: usize,
+    pub drain_timeout: u64,
+    pub lease_timeout: u64,
+}
@@ -0,0 +1,502 @@
+use anyhow::{anyhow, Context, Error};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::domain::request::document::generally::JobStatus;
+
+#[derive(Clone, Copy, Debug)]
+pub enum JobKind {
+    Thinktank,
+    Guideline,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            JobKind::Thinktank => "thinktank",
+            JobKind::Guideline => "guideline",
+        }
+    }
+}
+
+impl TryFrom<&str> for JobKind {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "thinktank" => Ok(JobKind::Thinktank),
+            "guideline" => Ok(JobKind::Guideline),
+            _ => Err(format!("{} is not a supported job kind", s)),
+        }
+    }
+}
+
+// 入库任务的阶段, 记录的是最近一个已经完成的步骤
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum JobStage {
+    Received,
+    Saved,
+    Converted,
+    Extracted,
+    Split,
+    Embedded,
+    Indexed,
+}
+
+impl JobStage {
+    pub fn as_str(&self) -> &str {
+        match self {
+            JobStage::Received => "received",
+            JobStage::Saved => "saved",
+            JobStage::Converted => "converted",
+            JobStage::Extracted => "extracted",
+            JobStage::Split => "split",
+            JobStage::Embedded => "embedded",
+            JobStage::Indexed => "indexed",
+        }
+    }
+}
+
+impl TryFrom<&str> for JobStage {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "received" => Ok(JobStage::Received),
+            "saved" => Ok(JobStage::Saved),
+            "converted" => Ok(JobStage::Converted),
+            "extracted" => Ok(JobStage::Extracted),
+            "split" => Ok(JobStage::Split),
+            "embedded" => Ok(JobStage::Embedded),
+            "indexed" => Ok(JobStage::Indexed),
+            _ => Err(format!("{} is not a supported job stage", s)),
+        }
+    }
+}
+
+#[derive(FromRow)]
+pub struct JobRecord {
+    pub id: Uuid,
+    pub uuid: String,
+    pub kind: String,
+    pub filename: String,
+    pub stage: String,
+    pub error: Option<String>,
+    pub error_kind: Option<String>,
+    pub slices: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub version: i32,
+    pub digest: Option<String>,
+    pub duplicate_of: Option<Uuid>,
+    pub metadata: Option<Value>,
+    pub claimed_at: Option<DateTime<Utc>>,
+    pub reindex: bool,
+}
+
+impl JobRecord {
+    // 任务状态不单独存储, 由阶段和错误信息推导得出
+    pub fn status(&self) -> JobStatus {
+        if self.error.is_some() {
+            JobStatus::Failed
+        } else if self.stage == JobStage::Indexed.as_str() {
+            JobStatus::Finished
+        } else {
+            JobStatus::Processing
+        }
+    }
+
+    pub fn handle(&self) -> Result<JobHandle, Error> {
+        let stage = JobStage::try_from(self.stage.as_str()).map_err(|error| anyhow!(error))?;
+        Ok(JobHandle {
+            id: self.id,
+            version: self.version,
+            stage,
+            reindex: self.reindex,
+        })
+    }
+}
+
+// 正在处理的入库任务及其最近完成的阶段, 后续每一步都通过任务主键更新任务阶段
+#[derive(Clone, Copy, Debug)]
+pub struct JobHandle {
+    pub id: Uuid,
+    pub version: i32,
+    pub stage: JobStage,
+    pub reindex: bool, // 重新入库的任务不复用内容相同的已入库文档
+}
+
+// 登记入库任务的结果, 同一文档已有正在入库的版本或者版本号被并发的登记占用时不登记新任务
+pub enum JobInsertion {
+    Inserted(JobHandle),
+    Busy(i32),
+    Duplicated,
+}
+
+// 登记入库任务并分配文档的新版本号, 版本号在同一类型同一文档主键下递增,
+// 同时保存入库所需的文档信息, 服务重启后可以据此继续入库;
+// 通过事务级的咨询锁串行化同一文档的登记, 检查正在入库的版本和分配版本号之间不会插入其他登记
+#[tracing::instrument(name = "Insert ingestion job", skip(pgpool, metadata))]
+pub async fn insert_job(
+    pgpool: &PgPool,
+    uuid: &str,
+    kind: JobKind,
+    filename: &str,
+    metadata: &Value,
+) -> Result<JobInsertion, Error> {
+    let mut transaction = pgpool
+        .begin()
+        .await
+        .context("Failed to begin transaction of inserting ingestion job")?;
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1 || ':' || $2, 0))")
+        .bind(kind.as_str())
+        .bind(uuid)
+        .execute(&mut *transaction)
+        .await
+        .with_context(|| format!("Failed to lock ingestion jobs of {}", uuid))?;
+
+    let busy: Option<(i32,)> = sqlx::query_as(
+        r#"
+        SELECT version FROM ingestion_jobs
+        WHERE kind = $1 AND uuid = $2 AND error IS NULL AND deleted_at IS NULL AND stage <> $3
+        ORDER BY created_at DESC LIMIT 1
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(uuid)
+    .bind(JobStage::Indexed.as_str())
+    .fetch_optional(&mut *transaction)
+    .await
+    .with_context(|| format!("Failed to select processing ingestion job of {}", uuid))?;
+    if let Some((version,)) = busy {
+        return Ok(JobInsertion::Busy(version));
+    }
+
+    let id = Uuid::new_v4();
+    let inserted: Result<(i32,), sqlx::Error> = sqlx::query_as(
+        r#"
+        INSERT INTO ingestion_jobs (id, uuid, kind, filename, stage, version, metadata)
+        SELECT $1, $2, $3, $4, $5, COALESCE(MAX(version), 0) + 1, $6
+        FROM ingestion_jobs WHERE kind = $3 AND uuid = $2
+        RETURNING version
+        "#,
+    )
+    .bind(id)
+    .bind(uuid)
+    .bind(kind.as_str())
+    .bind(filename)
+    .bind(JobStage::Received.as_str())
+    .bind(metadata)
+    .fetch_one(&mut *transaction)
+    .await;
+    // 咨询锁之外的写入(例如手工修复数据)仍然可能占用同一个版本号, 由唯一索引兜底
+    let (version,) = match inserted {
+        Err(sqlx::Error::Database(error)) if error.is_unique_violation() => {
+            return Ok(JobInsertion::Duplicated)
+        }
+        inserted => {
+            inserted.with_context(|| format!("Failed to insert ingestion job of {}", uuid))?
+        }
+    };
+    transaction
+        .commit()
+        .await
+        .with_context(|| format!("Failed to commit ingestion job of {}", uuid))?;
+
+    Ok(JobInsertion::Inserted(JobHandle {
+        id,
+        version,
+        stage: JobStage::Received,
+        reindex: false,
+    }))
+}
+
+// 领取入库任务, 已经被其他工作任务领取且租约没有过期的任务不会被重复领取
+#[tracing::instrument(name = "Claim ingestion job", skip(pgpool))]
+pub async fn claim_job(pgpool: &PgPool, id: Uuid, lease: u64) -> Result<Option<JobRecord>, Error> {
+    let sql = format!(
+        "UPDATE ingestion_jobs SET claimed_at = now(), updated_at = now() \
+         WHERE id = ( \
+             SELECT id FROM ingestion_jobs \
+             WHERE id = $1 AND error IS NULL AND deleted_at IS NULL AND stage <> $2 \
+             AND (claimed_at IS NULL OR claimed_at < now() - make_interval(secs => $3)) \
+             FOR UPDATE SKIP LOCKED \
+         ) \
+         RETURNING {}",
+        JOB_COLUMNS
+    );
+    sqlx::query_as::<_, JobRecord>(&sql)
+        .bind(id)
+        .bind(JobStage::Indexed.as_str())
+        .bind(lease as f64)
+        .fetch_optional(pgpool)
+        .await
+        .with_context(|| format!("Failed to claim ingestion job {}", id))
+}
+
+// 查询尚未完成且没有被领取(或租约已经过期)的入库任务, 按照登记顺序返回
+#[tracing::instrument(name = "Select pending ingestion jobs", skip(pgpool))]
+pub async fn select_pending_jobs(pgpool: &PgPool, lease: u64) -> Result<Vec<Uuid>, Error> {
+    let jobs: Vec<(Uuid,)> = sqlx::query_as(
+        r#"
+        SELECT id FROM ingestion_jobs
+        WHERE error IS NULL AND deleted_at IS NULL AND stage <> $1
+        AND (claimed_at IS NULL OR claimed_at < now() - make_interval(secs => $2))
+        ORDER BY created_at
+        "#,
+    )
+    .bind(JobStage::Indexed.as_str())
+    .bind(lease as f64)
+    .fetch_all(pgpool)
+    .await
+    .context("Failed to select pending ingestion jobs")?;
+    Ok(jobs.into_iter().map(|(id,)| id).collect())
+}
+
+// 释放租约已经过期的任务并返回这些任务, 领取任务的进程异常退出后由其他进程重新排队,
+// 释放后的任务没有领取时间, 不会在下一次检查时被重复排队
+#[tracing::instrument(name = "Release expired ingestion jobs", skip(pgpool))]
+pub async fn release_expired_jobs(pgpool: &PgPool, lease: u64) -> Result<Vec<Uuid>, Error> {
+    let mut jobs: Vec<(Uuid, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        UPDATE ingestion_jobs SET claimed_at = NULL, updated_at = now()
+        WHERE error IS NULL AND deleted_at IS NULL AND stage <> $1
+        AND claimed_at < now() - make_interval(secs => $2)
+        RETURNING id, created_at
+        "#,
+    )
+    .bind(JobStage::Indexed.as_str())
+    .bind(lease as f64)
+    .fetch_all(pgpool)
+    .await
+    .context("Failed to release expired ingestion jobs")?;
+    jobs.sort_by_key(|(_, created_at)| *created_at);
+    Ok(jobs.into_iter().map(|(id, _)| id).collect())
+}
+
+// 已经被领取的任务每完成一个阶段就续约一次, 避免耗时较长的任务被重复领取
+#[tracing::instrument(name = "Update ingestion job stage", skip(pgpool))]
+pub async fn update_stage(pgpool: &PgPool, id: Uuid, stage: JobStage) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        UPDATE ingestion_jobs
+        SET stage = $2,
+            updated_at = now(),
+            finished_at = CASE WHEN $3 THEN now() ELSE finished_at END,
+            claimed_at = CASE WHEN claimed_at IS NULL THEN NULL ELSE now() END
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(stage.as_str())
+    .bind(stage == JobStage::Indexed)
+    .execute(pgpool)
+    .await
+    .with_context(|| format!("Failed to update stage of ingestion job {}", id))?;
+    Ok(())
+}
+
+#[tracing::instrument(name = "Update ingestion job error", skip(pgpool, error))]
+pub async fn update_error(pgpool: &PgPool, id: Uuid, kind: &str, error: &str) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        UPDATE ingestion_jobs
+        SET error = $2, error_kind = $3, updated_at = now(), finished_at = now()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(error)
+    .bind(kind)
+    .execute(pgpool)
+    .await
+    .with_context(|| format!("Failed to update error of ingestion job {}", id))?;
+    Ok(())
+}
+
+// 将任务退回到指定阶段重新入库, 同时清除错误、领取状态和上次处理的结果,
+// 并标记为重新入库, 之后的处理不再复用内容相同的已入库文档
+#[tracing::instrument(name = "Reset ingestion job", skip(pgpool))]
+pub async fn reset_job(pgpool: &PgPool, id: Uuid, stage: JobStage) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        UPDATE ingestion_jobs
+        SET stage = $2, error = NULL, error_kind = NULL, slices = NULL, duplicate_of = NULL,
+            claimed_at = NULL, finished_at = NULL, reindex = TRUE, updated_at = now()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(stage.as_str())
+    .execute(pgpool)
+    .await
+    .with_context(|| format!("Failed to reset ingestion job {}", id))?;
+    Ok(())
+}
+
+#[tracing::instrument(name = "Update ingestion job slices", skip(pgpool))]
+pub async fn update_slices(pgpool: &PgPool, id: Uuid, slices: usize) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        UPDATE ingestion_jobs SET slices = $2, updated_at = now()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(slices as i32)
+    .execute(pgpool)
+    .await
+    .with_context(|| format!("Failed to update slices of ingestion job {}", id))?;
+    Ok(())
+}
+
+const JOB_COLUMNS: &str = "id, uuid, kind, filename, stage, error, error_kind, slices, version, \
+    digest, duplicate_of, metadata, claimed_at, reindex, created_at, updated_at, finished_at, \
+    deleted_at";
+
+// 同一个文档可能被多次上传, 只返回最近一次的入库任务
+#[tracing::instrument(name = "Select latest ingestion job", skip(pgpool))]
+pub async fn select_latest_job(
+    pgpool: &PgPool,
+    kind: JobKind,
+    uuid: &str,
+) -> Result<Option<JobRecord>, Error> {
+    let sql = format!(
+        "SELECT {} FROM ingestion_jobs WHERE kind = $1 AND uuid = $2 ORDER BY created_at DESC LIMIT 1",
+        JOB_COLUMNS
+    );
+    sqlx::query_as::<_, JobRecord>(&sql)
+        .bind(kind.as_str())
+        .bind(uuid)
+        .fetch_optional(pgpool)
+        .await
+        .with_context(|| format!("Failed to select ingestion job of {}", uuid))
+}
+
+fn push_status_filter(builder: &mut QueryBuilder<'_, Postgres>, status: Option<JobStatus>) {
+    match status {
+        Some(JobStatus::Failed) => builder.push(" AND error IS NOT NULL"),
+        Some(JobStatus::Finished) => builder
+            .push(" AND error IS NULL AND stage = ")
+            .push_bind(JobStage::Indexed.as_str()),
+        Some(JobStatus::Processing) => builder
+            .push(" AND error IS NULL AND stage <> ")
+            .push_bind(JobStage::Indexed.as_str()),
+        None => builder,
+    };
+}
+
+// 分页查询入库任务, 返回当前页的任务以及满足条件的任务总数
+#[tracing::instrument(name = "Select ingestion jobs", skip(pgpool))]
+pub async fn select_jobs(
+    pgpool: &PgPool,
+    kind: JobKind,
+    status: Option<JobStatus>,
+    offset: i64,
+    limit: u64,
+) -> Result<(Vec<JobRecord>, i64), Error> {
+    let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM ingestion_jobs WHERE kind = ");
+    builder.push_bind(kind.as_str());
+    push_status_filter(&mut builder, status);
+    let (total,): (i64,) = builder
+        .build_query_as()
+        .fetch_one(pgpool)
+        .await
+        .context("Failed to count ingestion jobs")?;
+
+    let mut builder = QueryBuilder::new(format!(
+        "SELECT {} FROM ingestion_jobs WHERE kind = ",
+        JOB_COLUMNS
+    ));
+    builder.push_bind(kind.as_str());
+    push_status_filter(&mut builder, status);
+    builder
+        .push(" ORDER BY created_at DESC OFFSET ")
+        .push_bind(offset)
+        .push(" LIMIT ")
+        .push_bind(limit as i64);
+    let jobs = builder
+        .build_query_as::<JobRecord>()
+        .fetch_all(pgpool)
+        .await
+        .context("Failed to select ingestion jobs")?;
+
+    Ok((jobs, total))
+}
+
+#[tracing::instrument(name = "Mark ingestion jobs deleted", skip(pgpool))]
+pub async fn mark_deleted(pgpool: &PgPool, kind: JobKind, uuid: &str) -> Result<u64, Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE ingestion_jobs SET deleted_at = now(), updated_at = now()
+        WHERE kind = $1 AND uuid = $2 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(uuid)
+    .execute(pgpool)
+    .await
+    .with_context(|| format!("Failed to mark ingestion jobs of {} deleted", uuid))?;
+    Ok(result.rows_affected())
+}
+
+#[tracing::instrument(name = "Update ingestion job digest", skip(pgpool))]
+pub async fn update_digest(pgpool: &PgPool, id: Uuid, digest: &str) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        UPDATE ingestion_jobs SET digest = $2, updated_at = now()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(digest)
+    .execute(pgpool)
+    .await
+    .with_context(|| format!("Failed to update digest of ingestion job {}", id))?;
+    Ok(())
+}
+
+#[tracing::instrument(name = "Update ingestion job duplicate", skip(pgpool))]
+pub async fn update_duplicate(pgpool: &PgPool, id: Uuid, source: Uuid) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        UPDATE ingestion_jobs SET duplicate_of = $2, updated_at = now()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(source)
+    .execute(pgpool)
+    .await
+    .with_context(|| format!("Failed to update duplicate of ingestion job {}", id))?;
+    Ok(())
+}
+
+// 查找内容相同且已经成功入库的文档, 优先返回最近一次入库的任务
+#[tracing::instrument(name = "Select duplicate ingestion job", skip(pgpool))]
+pub async fn select_duplicate(
+    pgpool: &PgPool,
+    kind: JobKind,
+    digest: &str,
+    exclude: Uuid,
+) -> Result<Option<JobRecord>, Error> {
+    let sql = format!(
+        "SELECT {} FROM ingestion_jobs \
+         WHERE kind = $1 AND digest = $2 AND id <> $3 AND stage = $4 \
+         AND error IS NULL AND deleted_at IS NULL \
+         ORDER BY created_at DESC LIMIT 1",
+        JOB_COLUMNS
+    );
+    sqlx::query_as::<_, JobRecord>(&sql)
+        .bind(kind.as_str())
+        .bind(digest)
+        .bind(exclude)
+        .bind(JobStage::Indexed.as_str())
+        .fetch_optional(pgpool)
+        .await
+        .with_context(|| format!("Failed to select duplicate ingestion job of {}", digest))
+}
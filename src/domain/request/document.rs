@@ -1,2 +1,3 @@
 pub mod generally;
+pub mod guideline;
 pub mod thinktank;
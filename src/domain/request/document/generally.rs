@@ -4,9 +4,13 @@ use std::ops::Deref;
 use std::path::Path;
 
 use actix_multipart::form::tempfile::TempFile;
+use serde::{Deserialize, Serialize};
 use tokio::fs;
+use unicode_normalization::UnicodeNormalization;
+use uuid::Uuid;
 
 use crate::blunder::document::ParseError;
+use crate::helper::sniffer::{self, FileType};
 
 #[derive(Clone, Debug)]
 pub enum Extension {
@@ -15,6 +19,46 @@ pub enum Extension {
     Xls,
     Docx,
     Xlsx,
+    Ppt,
+    Pptx,
+    Txt,
+    Md,
+    Csv,
+    Html,
+}
+
+impl Extension {
+    // 复合文档无法识别具体类型时, doc、xls和ppt都视为相符; 文本格式没有文件头, 只要求是UTF-8或GB18030等可识别编码的文本
+    pub fn conforms(&self, detected: FileType) -> bool {
+        match self {
+            Extension::Doc => matches!(detected, FileType::Doc | FileType::Ole),
+            Extension::Xls => matches!(detected, FileType::Xls | FileType::Ole),
+            Extension::Ppt => matches!(detected, FileType::Ppt | FileType::Ole),
+            Extension::Pdf => detected == FileType::Pdf,
+            Extension::Docx => detected == FileType::Docx,
+            Extension::Xlsx => detected == FileType::Xlsx,
+            Extension::Pptx => detected == FileType::Pptx,
+            Extension::Txt | Extension::Md | Extension::Csv | Extension::Html => {
+                detected == FileType::Text
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Extension::Doc => "doc",
+            Extension::Pdf => "pdf",
+            Extension::Xls => "xls",
+            Extension::Docx => "docx",
+            Extension::Xlsx => "xlsx",
+            Extension::Ppt => "ppt",
+            Extension::Pptx => "pptx",
+            Extension::Txt => "txt",
+            Extension::Md => "md",
+            Extension::Csv => "csv",
+            Extension::Html => "html",
+        }
+    }
 }
 
 impl TryFrom<&str> for Extension {
@@ -27,35 +71,101 @@ impl TryFrom<&str> for Extension {
             "xls" => Ok(Extension::Xls),
             "docx" => Ok(Extension::Docx),
             "xlsx" => Ok(Extension::Xlsx),
+            "ppt" => Ok(Extension::Ppt),
+            "pptx" => Ok(Extension::Pptx),
+            "txt" => Ok(Extension::Txt),
+            "md" | "markdown" => Ok(Extension::Md),
+            "csv" => Ok(Extension::Csv),
+            "html" | "htm" => Ok(Extension::Html),
             _ => Err(ParseError::InvalidExtension),
         }
     }
 }
 
-#[derive(Debug)]
+// 入库任务中保存的是文档名称字符串, 读取时重新解析扩展名
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub struct DocumentName(String, Extension);
 
+// 文档名称最多保留的字符数, 超出时截断扩展名之前的部分
+const MAXIMUM_NAME_LENGTH: usize = 128;
+// 在常见文件系统中不能出现在文件名称中的字符
+const RESERVED_CHARACTERS: &str = "<>:\"|?*";
+
 impl DocumentName {
+    // 文档名称用于构造缓存路径, 解析前先清理名称, 保证名称中不包含路径
     pub fn parse(s: String) -> Result<Self, ParseError> {
-        let extension = s
-            .to_lowercase()
-            .rsplit('.')
-            .next()
-            .ok_or_else(|| ParseError::MissingExtension)?
-            .try_into()?;
-        Ok(Self(s, extension))
+        let name = sanitize_name(&s);
+        if name.is_empty() {
+            return Err(ParseError::MissingFileName);
+        }
+        let extension = match name.rsplit_once('.') {
+            Some((_, extension)) if !extension.is_empty() => extension.try_into()?,
+            _ => return Err(ParseError::MissingExtension),
+        };
+        Ok(Self(name, extension))
     }
 
-    pub fn name(&self) -> Cow<str> {
+    pub fn name(&self) -> Cow<'_, str> {
         Cow::Borrowed(&self.0)
     }
 
-    pub fn extension(&self) -> Cow<Extension> {
+    pub fn extension(&self) -> Cow<'_, Extension> {
         Cow::Borrowed(&self.1)
     }
 }
 
-pub struct DocumentFile(DocumentName, TempFile);
+// 统一为NFC形式后只保留最后一级路径, 去除控制字符, 替换保留字符,
+// 去掉首尾的空白和点号, 避免生成隐藏文件以及`.`、`..`这样的特殊名称
+fn sanitize_name(s: &str) -> String {
+    let name: String = s.nfc().collect();
+    let name: String = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .map(|c| match RESERVED_CHARACTERS.contains(c) {
+            true => '_',
+            false => c,
+        })
+        .collect();
+    let name = name.trim_matches(|c: char| c.is_whitespace() || c == '.');
+    if name.chars().count() <= MAXIMUM_NAME_LENGTH {
+        return name.to_string();
+    }
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if extension.chars().count() < MAXIMUM_NAME_LENGTH => {
+            let length = MAXIMUM_NAME_LENGTH - extension.chars().count() - 1;
+            let stem: String = stem.chars().take(length).collect();
+            format!("{}.{}", stem.trim_end(), extension)
+        }
+        _ => name.chars().take(MAXIMUM_NAME_LENGTH).collect(),
+    }
+}
+
+impl TryFrom<String> for DocumentName {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(s)
+    }
+}
+
+impl From<DocumentName> for String {
+    fn from(name: DocumentName) -> Self {
+        name.0
+    }
+}
+
+// 两类文档的上传请求, 批量上传时据此统一校验和登记
+pub trait UploadDocument {
+    fn file(&self) -> &DocumentFile;
+    fn name(&self) -> &DocumentName;
+    fn uuid(&self) -> Uuid;
+}
+
+pub struct DocumentFile(DocumentName, TempFile, FileType);
 
 impl Deref for DocumentFile {
     type Target = DocumentName;
@@ -70,9 +180,34 @@ impl DocumentFile {
         let filename = tempfile
             .file_name
             .as_ref()
-            .ok_or_else(|| ParseError::MissingFileName)?
+            .ok_or(ParseError::MissingFileName)?
             .to_owned();
-        Ok(Self(DocumentName::parse(filename)?, tempfile))
+        let name = DocumentName::parse(filename)?;
+        let detected = sniffer::sniff(&mut tempfile.file.as_file())
+            .map_err(|error| ParseError::UnreadableFile(error.to_string()))?;
+        let document = Self(name, tempfile, detected);
+        document.conform(&document.0)?;
+        Ok(document)
+    }
+
+    pub fn path(&self) -> &Path {
+        self.1.file.path()
+    }
+
+    pub fn size(&self) -> usize {
+        self.1.size
+    }
+
+    // 入库时使用表单中的文档名称判断处理方式, 因此文档名称的扩展名也要与文件内容相符
+    pub fn conform(&self, name: &DocumentName) -> Result<(), ParseError> {
+        let extension = name.extension();
+        match extension.conforms(self.2) {
+            true => Ok(()),
+            false => Err(ParseError::MismatchedType(
+                extension.as_str().to_string(),
+                self.2.as_str().to_string(),
+            )),
+        }
     }
 
     pub async fn persist<T: AsRef<Path>>(&self, target: T) -> Result<(), io::Error> {
@@ -83,14 +218,110 @@ impl DocumentFile {
     }
 }
 
+// 文档标题去除首尾空白后不能为空, 长度不超过MAXIMUM_TITLE_LENGTH个字符
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DocumentTitle(String);
+
+const MAXIMUM_TITLE_LENGTH: usize = 200;
+
+impl DocumentTitle {
+    pub fn parse(s: String) -> Result<Self, ParseError> {
+        let title = s.trim();
+        if title.is_empty() {
+            return Err(ParseError::EmptyTitle);
+        }
+        if title.chars().count() > MAXIMUM_TITLE_LENGTH {
+            return Err(ParseError::TitleTooLong(MAXIMUM_TITLE_LENGTH));
+        }
+        Ok(Self(title.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for DocumentTitle {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(s)
+    }
+}
+
+impl From<DocumentTitle> for String {
+    fn from(title: DocumentTitle) -> Self {
+        title.0
+    }
+}
+
+// 取值必须属于配置的词表, 词表为空时不限制取值, 入库任务中保存的是已经校验过的取值
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DocumentTerm(String);
+
+impl DocumentTerm {
+    pub fn parse(s: String, vocabulary: &[String]) -> Option<Self> {
+        let term = s.trim();
+        let valid = !term.is_empty()
+            && (vocabulary.is_empty() || vocabulary.iter().any(|word| word == term));
+        valid.then(|| Self(term.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// 入库任务的状态, 数据库中由阶段和错误信息推导得出
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    Processing,
+    Finished,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            JobStatus::Processing => "processing",
+            JobStatus::Finished => "finished",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+impl TryFrom<&str> for JobStatus {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "processing" => Ok(JobStatus::Processing),
+            "finished" => Ok(JobStatus::Finished),
+            "failed" => Ok(JobStatus::Failed),
+            _ => Err(format!("{} is not a supported job status", s)),
+        }
+    }
+}
+
+pub struct JobsDomainRequest {
+    pub page: u64,                 // 页码
+    pub size: u64,                 // 每页数量
+    pub offset: i64,               // 跳过的任务数量
+    pub status: Option<JobStatus>, // 任务状态
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+
     use super::*;
 
     // 判断文件名称是否匹配特定的扩展名
     fn has_valid_extension<F: AsRef<str>>(filename: F) -> bool {
         matches!(
-            filename.as_ref().to_lowercase().split('.').last(),
+            filename.as_ref().to_lowercase().split('.').next_back(),
             Some("docx" | "doc" | "pdf" | "xls" | "xlsx")
         )
     }
@@ -115,13 +346,105 @@ mod tests {
 
     #[test]
     fn check_tempfile() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all("审计工作底稿".as_bytes()).unwrap();
         let tempfile = TempFile {
-            file_name: Some("example.docx".to_string()),
-            file: tempfile::NamedTempFile::new().unwrap(),
+            file_name: Some("example.txt".to_string()),
+            file,
             content_type: None,
             size: 100,
         };
         let document = DocumentFile::parse(tempfile).unwrap();
         println!("{}", document.name());
     }
+
+    #[test]
+    fn reject_mismatched_content() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"%PDF-1.7\n").unwrap();
+        let tempfile = TempFile {
+            file_name: Some("renamed.docx".to_string()),
+            file,
+            content_type: None,
+            size: 9,
+        };
+        assert!(matches!(
+            DocumentFile::parse(tempfile),
+            Err(ParseError::MismatchedType(claimed, detected)) if claimed == "docx" && detected == "pdf"
+        ));
+        assert!(matches!(
+            DocumentName::parse("审计报告".to_string()),
+            Err(ParseError::MissingExtension)
+        ));
+    }
+
+    #[test]
+    fn document_name_round_trip() {
+        let name: DocumentName = serde_json::from_str(r#""审计报告.PDF""#).unwrap();
+        assert!(matches!(name.extension().as_ref(), Extension::Pdf));
+        assert_eq!(serde_json::to_string(&name).unwrap(), r#""审计报告.PDF""#);
+        assert!(serde_json::from_str::<DocumentName>(r#""审计报告.exe""#).is_err());
+    }
+
+    #[test]
+    fn sanitize_traversal_names() {
+        let cases = [
+            ("../../etc/passwd.pdf", "passwd.pdf"),
+            ("..\\..\\windows\\报告.docx", "报告.docx"),
+            ("/tmp/../审计.txt", "审计.txt"),
+            ("  ..隐藏文件.pdf. ", "隐藏文件.pdf"),
+            ("a\u{0}b\nc:d?.md", "abc_d_.md"),
+            // 分解形式的e\u{0301}统一为组合形式的\u{e9}
+            ("re\u{0301}sume\u{0301}.pdf", "r\u{e9}sum\u{e9}.pdf"),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(
+                DocumentName::parse(name.to_string()).unwrap().name(),
+                expected
+            );
+        }
+
+        let long = DocumentName::parse(format!("{}.pdf", "审".repeat(300))).unwrap();
+        assert_eq!(long.name().chars().count(), MAXIMUM_NAME_LENGTH);
+        assert!(long.name().ends_with(".pdf"));
+
+        for name in ["..", "../", "a/..", "\u{7}"] {
+            assert!(matches!(
+                DocumentName::parse(name.to_string()),
+                Err(ParseError::MissingFileName)
+            ));
+        }
+    }
+
+    #[test]
+    fn parse_title_and_term() {
+        assert_eq!(
+            DocumentTitle::parse("  审计报告 ".to_string())
+                .unwrap()
+                .as_str(),
+            "审计报告"
+        );
+        assert!(matches!(
+            DocumentTitle::parse(" ".to_string()),
+            Err(ParseError::EmptyTitle)
+        ));
+        assert!(matches!(
+            DocumentTitle::parse("审".repeat(MAXIMUM_TITLE_LENGTH + 1)),
+            Err(ParseError::TitleTooLong(_))
+        ));
+
+        let vocabulary = vec!["财政".to_string(), "金融".to_string()];
+        assert!(DocumentTerm::parse("财政".to_string(), &vocabulary).is_some());
+        assert!(DocumentTerm::parse("其他".to_string(), &vocabulary).is_none());
+        assert!(DocumentTerm::parse("其他".to_string(), &[]).is_some());
+        assert!(DocumentTerm::parse("".to_string(), &[]).is_none());
+    }
+
+    #[test]
+    fn parse_extension_aliases() {
+        let name = DocumentName::parse("会议纪要.HTM".to_string()).unwrap();
+        assert!(matches!(name.extension().as_ref(), Extension::Html));
+        let name = DocumentName::parse("说明.markdown".to_string()).unwrap();
+        assert_eq!(name.extension().as_str(), "md");
+    }
 }
@@ -0,0 +1,61 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::request::document::generally::{
+    DocumentFile, DocumentName, DocumentTitle, UploadDocument,
+};
+
+pub struct UploadDomainRequest {
+    pub file: DocumentFile,  // 临时文档
+    pub name: DocumentName,  // 文档名称
+    pub uuid: Uuid,          // 文档主键
+    pub date: NaiveDate,     // 发布日期
+    pub head: DocumentTitle, // 文档标题
+    pub auth: String,        // 发布机构
+    pub exec: NaiveDate,     // 施行日期
+}
+
+// 批量上传的全部文档, 每个文档都已经通过校验且文档主键互不相同
+pub struct BatchUploadDomainRequest {
+    pub documents: Vec<UploadDomainRequest>,
+}
+
+impl UploadDocument for UploadDomainRequest {
+    fn file(&self) -> &DocumentFile {
+        &self.file
+    }
+
+    fn name(&self) -> &DocumentName {
+        &self.name
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+}
+
+impl UploadDomainRequest {
+    pub fn into_parts(self) -> (DocumentFile, IngestDomainRequest) {
+        let ingest = IngestDomainRequest {
+            name: self.name,
+            uuid: self.uuid,
+            date: self.date,
+            head: self.head,
+            auth: self.auth,
+            exec: self.exec,
+        };
+        (self.file, ingest)
+    }
+}
+
+// 保存在入库任务中的文档信息, 不包含临时文档, 服务重启后据此继续入库
+#[derive(Serialize, Deserialize)]
+pub struct IngestDomainRequest {
+    pub name: DocumentName,  // 文档名称
+    pub uuid: Uuid,          // 文档主键
+    pub date: NaiveDate,     // 发布日期
+    pub head: DocumentTitle, // 文档标题
+    pub auth: String,        // 发布机构
+    pub exec: NaiveDate,     // 施行日期
+}
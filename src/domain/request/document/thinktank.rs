@@ -1,12 +1,83 @@
-use crate::domain::request::document::generally::{DocumentFile, DocumentName};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::request::document::generally::{
+    DocumentFile, DocumentName, DocumentTerm, DocumentTitle, UploadDocument,
+};
 
 pub struct UploadDomainRequest {
-    pub file: DocumentFile, // 临时文档
-    pub name: DocumentName, // 文档名称
-    pub uuid: String,       // 文档主键
-    pub date: String,       // 文档日期
-    pub head: String,       // 文档标题
-    pub hold: String,       // 文档所属
-    pub area: String,       // 应用范围
-    pub stem: String,       // 文档来源
+    pub file: DocumentFile,  // 临时文档
+    pub name: DocumentName,  // 文档名称
+    pub uuid: Uuid,          // 文档主键
+    pub date: NaiveDate,     // 文档日期
+    pub head: DocumentTitle, // 文档标题
+    pub hold: String,        // 文档所属
+    pub area: DocumentTerm,  // 应用范围
+    pub stem: DocumentTerm,  // 文档来源
+}
+
+// 批量上传的全部文档, 每个文档都已经通过校验且文档主键互不相同
+pub struct BatchUploadDomainRequest {
+    pub documents: Vec<UploadDomainRequest>,
+}
+
+impl UploadDocument for UploadDomainRequest {
+    fn file(&self) -> &DocumentFile {
+        &self.file
+    }
+
+    fn name(&self) -> &DocumentName {
+        &self.name
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+}
+
+impl UploadDomainRequest {
+    pub fn into_parts(self) -> (DocumentFile, IngestDomainRequest) {
+        let ingest = IngestDomainRequest {
+            name: self.name,
+            uuid: self.uuid,
+            date: self.date,
+            head: self.head,
+            hold: self.hold,
+            area: self.area,
+            stem: self.stem,
+        };
+        (self.file, ingest)
+    }
+}
+
+// 保存在入库任务中的文档信息, 不包含临时文档, 服务重启后据此继续入库
+#[derive(Serialize, Deserialize)]
+pub struct IngestDomainRequest {
+    pub name: DocumentName,  // 文档名称
+    pub uuid: Uuid,          // 文档主键
+    pub date: NaiveDate,     // 文档日期
+    pub head: DocumentTitle, // 文档标题
+    pub hold: String,        // 文档所属
+    pub area: DocumentTerm,  // 应用范围
+    pub stem: DocumentTerm,  // 文档来源
+}
+
+// 从服务器目录导入文档, 目录和清单的实际路径在服务中解析, 每个文档在导入时再单独校验,
+// 单个文档出错不影响其他文档
+pub struct ImportDomainRequest {
+    pub directory: String, // 服务器上的导入目录
+    pub manifest: String,  // 文档清单路径, 相对路径以导入目录为起点
+    pub dry_run: bool,     // 只校验不入库
+}
+
+pub struct SearchDomainRequest {
+    pub text: String,            // 检索问题
+    pub topk: u64,               // 返回数量
+    pub hold: Option<String>,    // 文档所属
+    pub area: Option<String>,    // 应用范围
+    pub stem: Option<String>,    // 文档来源
+    pub from: Option<NaiveDate>, // 起始日期
+    pub till: Option<NaiveDate>, // 截止日期
+    pub vers: Option<i32>,       // 文档版本
 }
@@ -0,0 +1,2 @@
+pub mod generally;
+pub mod thinktank;
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::request::document::generally::JobStatus;
+
+pub struct JobDomainResponse {
+    pub id: Uuid,                        // 任务主键
+    pub uuid: String,                    // 文档主键
+    pub version: i32,                    // 文档版本
+    pub name: String,                    // 文档名称
+    pub stage: String,                   // 任务阶段
+    pub status: JobStatus,               // 任务状态
+    pub slices: Option<i32>,             // 切片数量
+    pub error: Option<String>,           // 错误信息
+    pub error_kind: Option<String>,      // 错误类型
+    pub digest: Option<String>,          // 内容哈希
+    pub duplicate: Option<Uuid>,         // 复用的任务
+    pub created: DateTime<Utc>,          // 创建时间
+    pub updated: DateTime<Utc>,          // 更新时间
+    pub finished: Option<DateTime<Utc>>, // 结束时间
+    pub elapsed: i64,                    // 耗时毫秒
+    pub deleted: Option<DateTime<Utc>>,  // 删除时间
+}
+
+pub struct JobsDomainResponse {
+    pub total: i64,
+    pub page: u64,
+    pub size: u64,
+    pub jobs: Vec<JobDomainResponse>,
+}
+
+// 批量上传中每个文档的登记结果, 登记失败时只有失败原因
+pub struct BatchJobDomainResponse {
+    pub name: String,          // 文档名称
+    pub uuid: String,          // 文档主键
+    pub job: Option<Uuid>,     // 任务主键
+    pub version: Option<i32>,  // 文档版本
+    pub error: Option<String>, // 登记失败的原因
+}
+
+pub struct BatchUploadDomainResponse {
+    pub jobs: Vec<BatchJobDomainResponse>,
+}
+
+// 删除文档需要分别清理向量、文件和记录, 每一步的结果单独记录, 任何一步失败都不影响其他步骤
+pub struct DeleteDomainResponse {
+    pub uuid: String,                // 文档主键
+    pub vectors: Result<(), String>, // 删除向量
+    pub files: Result<(), String>,   // 删除文件
+    pub records: Result<(), String>, // 标记记录
+}
+
+impl DeleteDomainResponse {
+    pub fn is_complete(&self) -> bool {
+        self.vectors.is_ok() && self.files.is_ok() && self.records.is_ok()
+    }
+}
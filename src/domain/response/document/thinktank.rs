@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub struct SearchDomainResponse {
+    pub slices: Vec<SliceDomainResponse>,
+}
+
+pub struct SliceDomainResponse {
+    pub uuid: String,          // 文档主键
+    pub name: String,          // 文档名称
+    pub date: String,          // 文档日期
+    pub head: String,          // 文档标题
+    pub hold: String,          // 文档所属
+    pub area: String,          // 应用范围
+    pub stem: String,          // 文档来源
+    pub vers: i32,             // 文档版本
+    pub index: u64,            // 切片序号
+    pub slice: String,         // 切片内容
+    pub sheet: Option<String>, // 切片所在工作表
+    pub headers: Vec<String>,  // 切片包含的列名
+    pub score: f32,            // 重排得分
+    pub similarity: f32,       // 向量相似度
+}
+
+pub struct ImportDomainResponse {
+    pub dry_run: bool,                // 只校验不入库
+    pub entries: Vec<ImportProgress>, // 每个文档的导入进度, 顺序与文档清单一致
+}
+
+// 导入进度按行追加到进度文件中, 重新导入时跳过已经登记的文档
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ImportProgress {
+    pub file: String,          // 文档路径
+    pub uuid: String,          // 文档主键
+    pub status: ImportStatus,  // 导入状态
+    pub job: Option<Uuid>,     // 任务主键
+    pub version: Option<i32>,  // 文档版本
+    pub error: Option<String>, // 失败原因
+}
+
+impl ImportProgress {
+    fn new(file: String, uuid: String, status: ImportStatus) -> Self {
+        Self {
+            file,
+            uuid,
+            status,
+            job: None,
+            version: None,
+            error: None,
+        }
+    }
+
+    pub fn pending(file: String, uuid: String) -> Self {
+        Self::new(file, uuid, ImportStatus::Pending)
+    }
+
+    pub fn valid(file: String, uuid: String) -> Self {
+        Self::new(file, uuid, ImportStatus::Valid)
+    }
+
+    pub fn queued(file: String, uuid: String, job: Uuid, version: i32) -> Self {
+        Self {
+            job: Some(job),
+            version: Some(version),
+            ..Self::new(file, uuid, ImportStatus::Queued)
+        }
+    }
+
+    pub fn failed(file: String, uuid: String, error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::new(file, uuid, ImportStatus::Failed)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    Pending, // 入库队列已满, 等待下次导入
+    Valid,   // 校验通过, 仅在只校验时出现
+    Queued,  // 已经登记入库任务
+    Failed,  // 校验或登记失败
+}
+
+impl ImportStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ImportStatus::Pending => "pending",
+            ImportStatus::Valid => "valid",
+            ImportStatus::Queued => "queued",
+            ImportStatus::Failed => "failed",
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct JobsRequest {
+    pub page: Option<u64>,      // 页码
+    pub size: Option<u64>,      // 每页数量
+    pub status: Option<String>, // 任务状态
+}
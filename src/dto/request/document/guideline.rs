@@ -0,0 +1,46 @@
+use actix_multipart::form::tempfile::TempFile;
+use actix_multipart::form::text::Text;
+use actix_multipart::form::MultipartForm;
+use serde::Deserialize;
+
+#[derive(MultipartForm)]
+pub struct UploadRequest {
+    pub file: TempFile,          // 临时文档
+    pub name: Text<String>,      // 文档名称
+    pub uuid: Text<String>,      // 文档主键
+    pub date: Text<String>,      // 发布日期
+    pub title: Text<String>,     // 文档标题
+    pub authority: Text<String>, // 发布机构
+    pub effective: Text<String>, // 施行日期
+}
+
+// 批量上传时文档清单中每个文档的元数据, 字段含义与单个上传相同
+#[derive(Deserialize)]
+pub struct UploadItem {
+    pub name: String,      // 文档名称
+    pub uuid: String,      // 文档主键
+    pub date: String,      // 发布日期
+    pub title: String,     // 文档标题
+    pub authority: String, // 发布机构
+    pub effective: String, // 施行日期
+}
+
+impl UploadRequest {
+    pub fn into_parts(self) -> (TempFile, UploadItem) {
+        let item = UploadItem {
+            name: self.name.into_inner(),
+            uuid: self.uuid.into_inner(),
+            date: self.date.into_inner(),
+            title: self.title.into_inner(),
+            authority: self.authority.into_inner(),
+            effective: self.effective.into_inner(),
+        };
+        (self.file, item)
+    }
+}
+
+#[derive(MultipartForm)]
+pub struct BatchUploadRequest {
+    pub files: Vec<TempFile>,   // 临时文档, 顺序与文档清单一致
+    pub manifest: Text<String>, // 文档清单, 由每个文档的元数据组成的JSON数组
+}
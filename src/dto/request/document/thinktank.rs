@@ -1,6 +1,7 @@
 use actix_multipart::form::tempfile::TempFile;
 use actix_multipart::form::text::Text;
 use actix_multipart::form::MultipartForm;
+use serde::Deserialize;
 
 #[derive(MultipartForm)]
 pub struct UploadRequest {
@@ -13,3 +14,68 @@ pub struct UploadRequest {
     pub range: Text<String>,  // 应用范围
     pub source: Text<String>, // 文档来源
 }
+
+// 批量上传时文档清单中每个文档的元数据, 字段含义与单个上传相同
+#[derive(Deserialize)]
+pub struct UploadItem {
+    pub name: String,   // 文档名称
+    pub uuid: String,   // 文档主键
+    pub date: String,   // 文档日期
+    pub title: String,  // 文档标题
+    pub owner: String,  // 文档所属
+    pub range: String,  // 应用范围
+    pub source: String, // 文档来源
+}
+
+// 导入时文档清单中的一个文档, 未设置文档名称时使用文件名称, 其他字段含义与单个上传相同
+#[derive(Deserialize)]
+pub struct ImportEntry {
+    pub file: String,         // 文档相对导入目录的路径
+    pub name: Option<String>, // 文档名称
+    pub uuid: String,         // 文档主键
+    pub date: String,         // 文档日期
+    pub title: String,        // 文档标题
+    pub owner: String,        // 文档所属
+    pub range: String,        // 应用范围
+    pub source: String,       // 文档来源
+}
+
+impl UploadRequest {
+    pub fn into_parts(self) -> (TempFile, UploadItem) {
+        let item = UploadItem {
+            name: self.name.into_inner(),
+            uuid: self.uuid.into_inner(),
+            date: self.date.into_inner(),
+            title: self.title.into_inner(),
+            owner: self.owner.into_inner(),
+            range: self.range.into_inner(),
+            source: self.source.into_inner(),
+        };
+        (self.file, item)
+    }
+}
+
+#[derive(MultipartForm)]
+pub struct BatchUploadRequest {
+    pub files: Vec<TempFile>,   // 临时文档, 顺序与文档清单一致
+    pub manifest: Text<String>, // 文档清单, 由每个文档的元数据组成的JSON数组
+}
+
+#[derive(Deserialize)]
+pub struct ImportRequest {
+    pub directory: String,     // 服务器上的导入目录
+    pub manifest: String,      // 文档清单路径, 相对路径以导入目录为起点
+    pub dry_run: Option<bool>, // 只校验不入库
+}
+
+#[derive(Deserialize)]
+pub struct SearchRequest {
+    pub question: String,       // 检索问题
+    pub limit: Option<u64>,     // 返回数量
+    pub owner: Option<String>,  // 文档所属
+    pub range: Option<String>,  // 应用范围
+    pub source: Option<String>, // 文档来源
+    pub start: Option<String>,  // 起始日期
+    pub until: Option<String>,  // 截止日期
+    pub version: Option<i32>,   // 文档版本, 未设置时只检索最新版本
+}
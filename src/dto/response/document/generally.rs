@@ -0,0 +1,116 @@
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use serde::Serialize;
+
+use crate::middleware::request;
+
+#[derive(Serialize)]
+pub struct JobResponse {
+    pub job: String,                // 任务主键
+    pub uuid: String,               // 文档主键
+    pub version: i32,               // 文档版本
+    pub name: String,               // 文档名称
+    pub stage: String,              // 任务阶段
+    pub status: String,             // 任务状态
+    pub slices: Option<i32>,        // 切片数量
+    pub error: Option<String>,      // 错误信息
+    pub error_kind: Option<String>, // 错误类型
+    pub digest: Option<String>,     // 内容哈希
+    pub duplicate: Option<String>,  // 复用的任务
+    pub created: String,            // 创建时间
+    pub updated: String,            // 更新时间
+    pub finished: Option<String>,   // 结束时间
+    pub elapsed: i64,               // 耗时毫秒
+    pub deleted: Option<String>,    // 删除时间
+}
+
+#[derive(Serialize)]
+pub struct JobsResponse {
+    pub total: i64,
+    pub page: u64,
+    pub size: u64,
+    pub jobs: Vec<JobResponse>,
+}
+
+#[derive(Serialize)]
+pub struct DeleteStepResponse {
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DeleteResponse {
+    pub uuid: String,                // 文档主键
+    pub vectors: DeleteStepResponse, // 删除向量
+    pub files: DeleteStepResponse,   // 删除文件
+    pub records: DeleteStepResponse, // 标记记录
+}
+
+#[derive(Serialize)]
+pub struct UploadResponse {
+    pub job: String,  // 任务主键
+    pub version: i32, // 文档版本
+}
+
+#[derive(Serialize)]
+pub struct BatchUploadResponse {
+    pub jobs: Vec<BatchJobResponse>, // 每个文档的登记结果, 顺序与文档清单一致
+}
+
+#[derive(Serialize)]
+pub struct BatchJobResponse {
+    pub name: String,          // 文档名称
+    pub uuid: String,          // 文档主键
+    pub job: Option<String>,   // 任务主键
+    pub version: Option<i32>,  // 文档版本
+    pub error: Option<String>, // 登记失败的原因
+}
+
+impl BatchUploadResponse {
+    pub fn is_complete(&self) -> bool {
+        self.jobs.iter().all(|job| job.error.is_none())
+    }
+}
+
+/// 所有接口统一使用的响应格式, code与HTTP状态码保持一致
+#[derive(Serialize)]
+pub struct DocumentResponse<T> {
+    pub code: u16,
+    pub message: String,
+    pub data: Option<T>,
+    pub request_id: String,
+}
+
+impl<T: Serialize> DocumentResponse<T> {
+    pub fn new(status: StatusCode, message: impl Into<String>, data: Option<T>) -> Self {
+        Self {
+            code: status.as_u16(),
+            message: message.into(),
+            data,
+            request_id: request::request_id(),
+        }
+    }
+
+    pub fn into_response(self) -> HttpResponse {
+        let status = StatusCode::from_u16(self.code).unwrap_or(StatusCode::OK);
+        HttpResponse::build(status).json(self)
+    }
+
+    pub fn success(status: StatusCode, data: T) -> HttpResponse {
+        Self::new(status, "成功", Some(data)).into_response()
+    }
+
+    // 部分操作失败时返回207和每一项的结果, 由前端决定是否重试失败的部分
+    pub fn partial(status: StatusCode, complete: bool, message: &str, data: T) -> HttpResponse {
+        match complete {
+            true => Self::success(status, data),
+            false => Self::new(StatusCode::MULTI_STATUS, message, Some(data)).into_response(),
+        }
+    }
+}
+
+impl DocumentResponse<()> {
+    pub fn failure(status: StatusCode, message: impl Into<String>) -> HttpResponse {
+        Self::new(status, message, None).into_response()
+    }
+}
@@ -0,0 +1,53 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct SearchResponse {
+    pub slices: Vec<SliceResponse>,
+}
+
+#[derive(Serialize)]
+pub struct SliceResponse {
+    pub uuid: String,    // 文档主键
+    pub name: String,    // 文档名称
+    pub date: String,    // 文档日期
+    pub title: String,   // 文档标题
+    pub owner: String,   // 文档所属
+    pub range: String,   // 应用范围
+    pub source: String,  // 文档来源
+    pub version: i32,    // 文档版本
+    pub index: u64,      // 切片序号
+    pub content: String, // 切片内容
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<String>, // 切片所在工作表
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<String>, // 切片包含的列名
+    pub score: f32,      // 重排得分
+    pub similarity: f32, // 向量相似度
+}
+
+#[derive(Serialize)]
+pub struct ImportResponse {
+    pub dry_run: bool,                     // 只校验不入库
+    pub total: usize,                      // 文档总数
+    pub queued: usize,                     // 已经登记的文档数量
+    pub valid: usize,                      // 校验通过的文档数量
+    pub failed: usize,                     // 失败的文档数量
+    pub pending: usize,                    // 等待下次导入的文档数量
+    pub entries: Vec<ImportEntryResponse>, // 每个文档的导入进度
+}
+
+#[derive(Serialize)]
+pub struct ImportEntryResponse {
+    pub file: String,          // 文档路径
+    pub uuid: String,          // 文档主键
+    pub status: String,        // 导入状态
+    pub job: Option<String>,   // 任务主键
+    pub version: Option<i32>,  // 文档版本
+    pub error: Option<String>, // 失败原因
+}
+
+impl ImportResponse {
+    pub fn is_complete(&self) -> bool {
+        self.failed == 0 && self.pending == 0
+    }
+}
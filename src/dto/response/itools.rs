@@ -0,0 +1,10 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct CircuitResponse {
+    pub endpoint: String,       // 代理服务
+    pub state: String,          // 熔断状态
+    pub failures: u32,          // 连续失败次数
+    pub opened: Option<String>, // 熔断时间
+    pub retry_after: u64,       // 剩余熔断秒数
+}
@@ -0,0 +1,331 @@
+use std::collections::HashSet;
+
+use actix_multipart::form::tempfile::TempFile;
+use actix_multipart::form::text::Text;
+use chrono::{NaiveDate, Utc};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+use crate::blunder::document::ParseError;
+use crate::configuration::limits::LimitSettings;
+use crate::database::ingestion::JobRecord;
+use crate::domain::request::document::generally::{JobStatus, JobsDomainRequest};
+use crate::domain::response::document::generally::{
+    BatchJobDomainResponse, BatchUploadDomainResponse, DeleteDomainResponse, JobDomainResponse,
+    JobsDomainResponse,
+};
+use crate::dto::request::document::generally::JobsRequest;
+use crate::dto::response::document::generally::{
+    BatchJobResponse, BatchUploadResponse, DeleteResponse, DeleteStepResponse, JobResponse,
+    JobsResponse,
+};
+
+pub fn inner<T: DeserializeOwned>(text: Text<T>) -> T {
+    text.into_inner()
+}
+
+// 空白字符串视为未设置
+pub fn filter(value: Option<String>) -> Option<String> {
+    value
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+// 常用的日期格式, 例如: 2024-01-31、2024/1/31、2024.01.31、20240131、2024年1月31日
+const DATE_FORMATS: [&str; 5] = ["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d", "%Y%m%d", "%Y年%m月%d日"];
+
+pub fn date(value: String) -> Result<NaiveDate, ParseError> {
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value.trim(), format).ok())
+        .ok_or(ParseError::InvalidDate(value))
+}
+
+pub fn uuid(value: String) -> Result<Uuid, ParseError> {
+    Uuid::parse_str(value.trim()).map_err(|_| ParseError::InvalidUuid(value))
+}
+
+// 解析批量上传的文档清单, 清单中的元数据按顺序与上传的文档一一对应
+pub fn manifest<T: DeserializeOwned>(
+    files: Vec<TempFile>,
+    manifest: &str,
+    limits: &LimitSettings,
+) -> Result<Vec<(TempFile, T)>, ParseError> {
+    if files.is_empty() {
+        return Err(ParseError::EmptyBatch);
+    }
+    if files.len() > limits.max_batch_files {
+        return Err(ParseError::TooManyFiles(limits.max_batch_files));
+    }
+    let items: Vec<T> = serde_json::from_str(manifest)
+        .map_err(|error| ParseError::InvalidManifest(error.to_string()))?;
+    if files.len() != items.len() {
+        return Err(ParseError::ManifestMismatch(files.len(), items.len()));
+    }
+    Ok(files.into_iter().zip(items).collect())
+}
+
+// 逐个校验批量上传的文档, 出错时指出是第几个文档, 同一批次中的文档主键不能重复
+pub fn batch<I, T>(
+    items: Vec<I>,
+    parse: impl Fn(I) -> Result<T, ParseError>,
+    key: impl Fn(&T) -> Uuid,
+) -> Result<Vec<T>, ParseError> {
+    let mut uuids = HashSet::new();
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let document = parse(item)
+                .map_err(|error| ParseError::InvalidBatchItem(index + 1, Box::new(error)))?;
+            let uuid = key(&document);
+            match uuids.insert(uuid) {
+                true => Ok(document),
+                false => Err(ParseError::DuplicateUuid(uuid.to_string())),
+            }
+        })
+        .collect()
+}
+
+// 任务列表的默认每页数量、最大每页数量和最大页码
+const DEFAULT_PAGE_SIZE: u64 = 20;
+const MAXIMUM_PAGE_SIZE: u64 = 100;
+const MAXIMUM_PAGE: u64 = 100_000;
+
+impl TryFrom<JobsRequest> for JobsDomainRequest {
+    type Error = ParseError;
+
+    fn try_from(value: JobsRequest) -> Result<Self, Self::Error> {
+        let page = value.page.unwrap_or(1);
+        let size = value.size.unwrap_or(DEFAULT_PAGE_SIZE);
+        let invalid = ParseError::InvalidPagination(MAXIMUM_PAGE, MAXIMUM_PAGE_SIZE);
+        if !(1..=MAXIMUM_PAGE).contains(&page) || !(1..=MAXIMUM_PAGE_SIZE).contains(&size) {
+            return Err(invalid);
+        }
+        let offset = (page - 1)
+            .checked_mul(size)
+            .and_then(|offset| i64::try_from(offset).ok())
+            .ok_or(invalid)?;
+        let status = filter(value.status)
+            .map(|s| JobStatus::try_from(s.as_str()).map_err(|_| ParseError::InvalidStatus(s)))
+            .transpose()?;
+        Ok(Self {
+            page,
+            size,
+            offset,
+            status,
+        })
+    }
+}
+
+impl From<JobRecord> for JobDomainResponse {
+    fn from(value: JobRecord) -> Self {
+        let status = value.status();
+        let elapsed =
+            (value.finished_at.unwrap_or_else(Utc::now) - value.created_at).num_milliseconds();
+        Self {
+            id: value.id,
+            uuid: value.uuid,
+            version: value.version,
+            name: value.filename,
+            stage: value.stage,
+            status,
+            slices: value.slices,
+            error: value.error,
+            error_kind: value.error_kind,
+            digest: value.digest,
+            duplicate: value.duplicate_of,
+            created: value.created_at,
+            updated: value.updated_at,
+            finished: value.finished_at,
+            elapsed,
+            deleted: value.deleted_at,
+        }
+    }
+}
+
+impl From<JobDomainResponse> for JobResponse {
+    fn from(value: JobDomainResponse) -> Self {
+        Self {
+            job: value.id.to_string(),
+            uuid: value.uuid,
+            version: value.version,
+            name: value.name,
+            stage: value.stage,
+            status: value.status.as_str().to_string(),
+            slices: value.slices,
+            error: value.error,
+            error_kind: value.error_kind,
+            digest: value.digest,
+            duplicate: value.duplicate.map(|duplicate| duplicate.to_string()),
+            created: value.created.to_rfc3339(),
+            updated: value.updated.to_rfc3339(),
+            finished: value.finished.map(|finished| finished.to_rfc3339()),
+            elapsed: value.elapsed,
+            deleted: value.deleted.map(|deleted| deleted.to_rfc3339()),
+        }
+    }
+}
+
+impl From<JobsDomainResponse> for JobsResponse {
+    fn from(value: JobsDomainResponse) -> Self {
+        Self {
+            total: value.total,
+            page: value.page,
+            size: value.size,
+            jobs: value.jobs.into_iter().map(JobResponse::from).collect(),
+        }
+    }
+}
+
+impl From<BatchJobDomainResponse> for BatchJobResponse {
+    fn from(value: BatchJobDomainResponse) -> Self {
+        Self {
+            name: value.name,
+            uuid: value.uuid,
+            job: value.job.map(|job| job.to_string()),
+            version: value.version,
+            error: value.error,
+        }
+    }
+}
+
+impl From<BatchUploadDomainResponse> for BatchUploadResponse {
+    fn from(value: BatchUploadDomainResponse) -> Self {
+        Self {
+            jobs: value.jobs.into_iter().map(BatchJobResponse::from).collect(),
+        }
+    }
+}
+
+impl From<Result<(), String>> for DeleteStepResponse {
+    fn from(value: Result<(), String>) -> Self {
+        Self {
+            success: value.is_ok(),
+            error: value.err(),
+        }
+    }
+}
+
+impl From<DeleteDomainResponse> for DeleteResponse {
+    fn from(value: DeleteDomainResponse) -> Self {
+        Self {
+            uuid: value.uuid,
+            vectors: value.vectors.into(),
+            files: value.files.into(),
+            records: value.records.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_jobs_request() {
+        let domain = JobsDomainRequest::try_from(JobsRequest {
+            page: Some(3),
+            size: None,
+            status: Some("Failed".to_string()),
+        })
+        .unwrap();
+        assert_eq!(domain.offset, 2 * DEFAULT_PAGE_SIZE as i64);
+        assert_eq!(domain.status, Some(JobStatus::Failed));
+
+        let page = JobsDomainRequest::try_from(JobsRequest {
+            page: Some(0),
+            size: None,
+            status: None,
+        });
+        assert!(matches!(page, Err(ParseError::InvalidPagination(..))));
+
+        let page = JobsDomainRequest::try_from(JobsRequest {
+            page: Some(u64::MAX),
+            size: Some(MAXIMUM_PAGE_SIZE),
+            status: None,
+        });
+        assert!(matches!(page, Err(ParseError::InvalidPagination(..))));
+
+        let status = JobsDomainRequest::try_from(JobsRequest {
+            page: None,
+            size: None,
+            status: Some("pending".to_string()),
+        });
+        assert!(matches!(status, Err(ParseError::InvalidStatus(_))));
+    }
+
+    #[test]
+    fn parse_batch_manifest() {
+        let limits = LimitSettings {
+            max_file_size: 1 << 20,
+            file_sizes: Default::default(),
+            max_decompressed_size: 1 << 20,
+            max_pages: 100,
+            max_sheets: 10,
+            max_batch_files: 2,
+            max_batch_size: 1 << 20,
+        };
+        let files = |count: usize| {
+            (0..count)
+                .map(|_| TempFile {
+                    file_name: Some("example.txt".to_string()),
+                    file: tempfile::NamedTempFile::new().unwrap(),
+                    content_type: None,
+                    size: 0,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let items = manifest::<String>(files(2), r#"["a", "b"]"#, &limits).unwrap();
+        assert_eq!(items[1].1, "b");
+        assert!(matches!(
+            manifest::<String>(files(2), r#"["a"]"#, &limits),
+            Err(ParseError::ManifestMismatch(2, 1))
+        ));
+        assert!(matches!(
+            manifest::<String>(files(3), "[]", &limits),
+            Err(ParseError::TooManyFiles(2))
+        ));
+        assert!(matches!(
+            manifest::<String>(files(1), "{", &limits),
+            Err(ParseError::InvalidManifest(_))
+        ));
+
+        let first = Uuid::new_v4();
+        let parse = |item: &str| match item {
+            "bad" => Err(ParseError::EmptyTitle),
+            _ => Ok(first),
+        };
+        assert!(matches!(
+            batch(vec!["ok", "bad"], parse, |uuid| *uuid),
+            Err(ParseError::InvalidBatchItem(2, _))
+        ));
+        assert!(matches!(
+            batch(vec!["ok", "ok"], parse, |uuid| *uuid),
+            Err(ParseError::DuplicateUuid(_))
+        ));
+    }
+
+    #[test]
+    fn parse_common_date_formats() {
+        let expected = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        for value in [
+            "2024-01-31",
+            " 2024/1/31",
+            "2024.01.31",
+            "20240131",
+            "2024年1月31日",
+        ] {
+            assert_eq!(date(value.to_string()).unwrap(), expected);
+        }
+        assert!(matches!(
+            date("2024-02-30".to_string()),
+            Err(ParseError::InvalidDate(_))
+        ));
+        assert!(matches!(
+            uuid("not-a-uuid".to_string()),
+            Err(ParseError::InvalidUuid(_))
+        ));
+    }
+}
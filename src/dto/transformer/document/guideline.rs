@@ -0,0 +1,51 @@
+use actix_multipart::form::tempfile::TempFile;
+
+use crate::blunder::document::ParseError;
+use crate::configuration::common::CommonSettings;
+use crate::domain::request::document::generally::{DocumentFile, DocumentName, DocumentTitle};
+use crate::domain::request::document::guideline::{BatchUploadDomainRequest, UploadDomainRequest};
+use crate::dto::request::document::guideline::{BatchUploadRequest, UploadItem, UploadRequest};
+use crate::dto::transformer::document::generally::{batch, date, filter, manifest, uuid};
+
+impl TryFrom<(TempFile, UploadItem)> for UploadDomainRequest {
+    type Error = ParseError;
+
+    fn try_from((file, item): (TempFile, UploadItem)) -> Result<Self, Self::Error> {
+        let file = DocumentFile::parse(file)?;
+        let name = DocumentName::parse(item.name)?;
+        file.conform(&name)?;
+        Ok(Self {
+            file,
+            name,
+            uuid: uuid(item.uuid)?,
+            date: date(item.date)?,
+            head: DocumentTitle::parse(item.title)?,
+            auth: filter(Some(item.authority)).ok_or(ParseError::EmptyAuthority)?,
+            exec: date(item.effective)?,
+        })
+    }
+}
+
+impl TryFrom<UploadRequest> for UploadDomainRequest {
+    type Error = ParseError;
+
+    fn try_from(value: UploadRequest) -> Result<Self, Self::Error> {
+        value.into_parts().try_into()
+    }
+}
+
+impl TryFrom<(BatchUploadRequest, &CommonSettings)> for BatchUploadDomainRequest {
+    type Error = ParseError;
+
+    fn try_from(
+        (value, common): (BatchUploadRequest, &CommonSettings),
+    ) -> Result<Self, Self::Error> {
+        let items = manifest::<UploadItem>(value.files, &value.manifest, &common.limits)?;
+        let documents = batch(
+            items,
+            UploadDomainRequest::try_from,
+            |document: &UploadDomainRequest| document.uuid,
+        )?;
+        Ok(Self { documents })
+    }
+}
@@ -1,28 +1,267 @@
-use actix_multipart::form::text::Text;
-use serde::de::DeserializeOwned;
+use actix_multipart::form::tempfile::TempFile;
 
 use crate::blunder::document::ParseError;
-use crate::domain::request::document::generally::{DocumentFile, DocumentName};
-use crate::domain::request::document::thinktank::UploadDomainRequest;
-use crate::dto::request::document::thinktank::UploadRequest;
+use crate::configuration::common::CommonSettings;
+use crate::configuration::vocabulary::VocabularySettings;
+use crate::domain::request::document::generally::{
+    DocumentFile, DocumentName, DocumentTerm, DocumentTitle,
+};
+use crate::domain::request::document::thinktank::{
+    BatchUploadDomainRequest, ImportDomainRequest, SearchDomainRequest, UploadDomainRequest,
+};
+use crate::domain::response::document::thinktank::{
+    ImportDomainResponse, ImportProgress, ImportStatus, SearchDomainResponse, SliceDomainResponse,
+};
+use crate::dto::request::document::thinktank::{
+    BatchUploadRequest, ImportEntry, ImportRequest, SearchRequest, UploadItem, UploadRequest,
+};
+use crate::dto::response::document::thinktank::{
+    ImportEntryResponse, ImportResponse, SearchResponse, SliceResponse,
+};
+use crate::dto::transformer::document::generally::{batch, date, filter, manifest, uuid};
 
-fn inner<T: DeserializeOwned>(text: Text<T>) -> T {
-    text.into_inner()
+// 检索结果数量的默认值和最大值
+const DEFAULT_LIMIT: u64 = 5;
+const MAXIMUM_LIMIT: u64 = 50;
+
+// 应用范围和文档来源需要按照配置的词表校验
+impl TryFrom<(TempFile, UploadItem, &VocabularySettings)> for UploadDomainRequest {
+    type Error = ParseError;
+
+    fn try_from(
+        (file, item, vocabulary): (TempFile, UploadItem, &VocabularySettings),
+    ) -> Result<Self, Self::Error> {
+        let file = DocumentFile::parse(file)?;
+        let name = DocumentName::parse(item.name)?;
+        file.conform(&name)?;
+        Ok(Self {
+            file,
+            name,
+            uuid: uuid(item.uuid)?,
+            date: date(item.date)?,
+            head: DocumentTitle::parse(item.title)?,
+            hold: item.owner.trim().to_string(),
+            area: term(item.range, &vocabulary.areas, ParseError::InvalidArea)?,
+            stem: term(item.source, &vocabulary.sources, ParseError::InvalidSource)?,
+        })
+    }
+}
+
+impl TryFrom<(UploadRequest, &VocabularySettings)> for UploadDomainRequest {
+    type Error = ParseError;
+
+    fn try_from(
+        (value, vocabulary): (UploadRequest, &VocabularySettings),
+    ) -> Result<Self, Self::Error> {
+        let (file, item) = value.into_parts();
+        (file, item, vocabulary).try_into()
+    }
+}
+
+impl TryFrom<(BatchUploadRequest, &CommonSettings)> for BatchUploadDomainRequest {
+    type Error = ParseError;
+
+    fn try_from(
+        (value, common): (BatchUploadRequest, &CommonSettings),
+    ) -> Result<Self, Self::Error> {
+        let items = manifest::<UploadItem>(value.files, &value.manifest, &common.limits)?;
+        let documents = batch(
+            items,
+            |(file, item)| (file, item, &common.vocabulary).try_into(),
+            |document: &UploadDomainRequest| document.uuid,
+        )?;
+        Ok(Self { documents })
+    }
+}
+
+// 导入目录和文档清单需要访问文件系统, 实际路径和清单内容由服务解析和校验
+impl From<ImportRequest> for ImportDomainRequest {
+    fn from(value: ImportRequest) -> Self {
+        Self {
+            directory: value.directory.trim().to_string(),
+            manifest: value.manifest.trim().to_string(),
+            dry_run: value.dry_run.unwrap_or(false),
+        }
+    }
+}
+
+// 导入的文档复制为临时文档后与上传的文档经过相同的校验, 文档名称默认为文件名称
+impl TryFrom<(TempFile, ImportEntry, &VocabularySettings)> for UploadDomainRequest {
+    type Error = ParseError;
+
+    fn try_from(
+        (file, entry, vocabulary): (TempFile, ImportEntry, &VocabularySettings),
+    ) -> Result<Self, Self::Error> {
+        let name = filter(entry.name)
+            .or_else(|| file.file_name.clone())
+            .unwrap_or_default();
+        let item = UploadItem {
+            name,
+            uuid: entry.uuid,
+            date: entry.date,
+            title: entry.title,
+            owner: entry.owner,
+            range: entry.range,
+            source: entry.source,
+        };
+        (file, item, vocabulary).try_into()
+    }
+}
+
+fn term(
+    value: String,
+    vocabulary: &[String],
+    error: fn(String) -> ParseError,
+) -> Result<DocumentTerm, ParseError> {
+    DocumentTerm::parse(value.clone(), vocabulary).ok_or_else(|| error(value))
 }
 
-impl TryFrom<UploadRequest> for UploadDomainRequest {
+impl TryFrom<SearchRequest> for SearchDomainRequest {
     type Error = ParseError;
 
-    fn try_from(value: UploadRequest) -> Result<Self, Self::Error> {
+    fn try_from(value: SearchRequest) -> Result<Self, Self::Error> {
+        let text = value.question.trim().to_string();
+        if text.is_empty() {
+            return Err(ParseError::EmptyQuestion);
+        }
+
+        let topk = value.limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAXIMUM_LIMIT).contains(&topk) {
+            return Err(ParseError::InvalidLimit(MAXIMUM_LIMIT));
+        }
+
+        let from = filter(value.start).map(date).transpose()?;
+        let till = filter(value.until).map(date).transpose()?;
+        if matches!((from, till), (Some(from), Some(till)) if from > till) {
+            return Err(ParseError::InvalidDateRange);
+        }
+
         Ok(Self {
-            file: DocumentFile::parse(value.file)?,
-            name: DocumentName::parse(inner(value.name))?,
-            uuid: inner(value.uuid),
-            date: inner(value.date),
-            head: inner(value.title),
-            hold: inner(value.owner),
-            area: inner(value.range),
-            stem: inner(value.source),
+            text,
+            topk,
+            hold: filter(value.owner),
+            area: filter(value.range),
+            stem: filter(value.source),
+            from,
+            till,
+            vers: value.version,
         })
     }
 }
+
+impl From<SliceDomainResponse> for SliceResponse {
+    fn from(value: SliceDomainResponse) -> Self {
+        Self {
+            uuid: value.uuid,
+            name: value.name,
+            date: value.date,
+            title: value.head,
+            owner: value.hold,
+            range: value.area,
+            source: value.stem,
+            version: value.vers,
+            index: value.index,
+            content: value.slice,
+            sheet: value.sheet,
+            headers: value.headers,
+            score: value.score,
+            similarity: value.similarity,
+        }
+    }
+}
+
+impl From<SearchDomainResponse> for SearchResponse {
+    fn from(value: SearchDomainResponse) -> Self {
+        Self {
+            slices: value.slices.into_iter().map(SliceResponse::from).collect(),
+        }
+    }
+}
+
+impl From<ImportProgress> for ImportEntryResponse {
+    fn from(value: ImportProgress) -> Self {
+        Self {
+            file: value.file,
+            uuid: value.uuid,
+            status: value.status.as_str().to_string(),
+            job: value.job.map(|job| job.to_string()),
+            version: value.version,
+            error: value.error,
+        }
+    }
+}
+
+impl From<ImportDomainResponse> for ImportResponse {
+    fn from(value: ImportDomainResponse) -> Self {
+        let count = |status| {
+            value
+                .entries
+                .iter()
+                .filter(|entry| entry.status == status)
+                .count()
+        };
+        Self {
+            dry_run: value.dry_run,
+            total: value.entries.len(),
+            queued: count(ImportStatus::Queued),
+            valid: count(ImportStatus::Valid),
+            failed: count(ImportStatus::Failed),
+            pending: count(ImportStatus::Pending),
+            entries: value
+                .entries
+                .into_iter()
+                .map(ImportEntryResponse::from)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn search_request(question: &str, start: Option<&str>, until: Option<&str>) -> SearchRequest {
+        SearchRequest {
+            question: question.to_string(),
+            limit: None,
+            owner: Some(" ".to_string()),
+            range: None,
+            source: None,
+            start: start.map(String::from),
+            until: until.map(String::from),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn parse_search_request() {
+        let domain = SearchDomainRequest::try_from(search_request(
+            " 审计发现 ",
+            Some("2024-01-01"),
+            Some("2024-12-31"),
+        ))
+        .unwrap();
+        assert_eq!(domain.text, "审计发现");
+        assert_eq!(domain.topk, DEFAULT_LIMIT);
+        assert!(domain.hold.is_none());
+        assert_eq!(domain.from, NaiveDate::from_ymd_opt(2024, 1, 1));
+    }
+
+    #[test]
+    fn reject_invalid_search_request() {
+        let empty = SearchDomainRequest::try_from(search_request("  ", None, None));
+        assert!(matches!(empty, Err(ParseError::EmptyQuestion)));
+
+        let date = SearchDomainRequest::try_from(search_request("审计", Some("2024/13/01"), None));
+        assert!(matches!(date, Err(ParseError::InvalidDate(_))));
+
+        let range = SearchDomainRequest::try_from(search_request(
+            "审计",
+            Some("2024-12-31"),
+            Some("2024-01-01"),
+        ));
+        assert!(matches!(range, Err(ParseError::InvalidDateRange)));
+    }
+}
@@ -0,0 +1,14 @@
+use crate::dto::response::itools::CircuitResponse;
+use crate::helper::proxy::CircuitSnapshot;
+
+impl From<CircuitSnapshot> for CircuitResponse {
+    fn from(value: CircuitSnapshot) -> Self {
+        Self {
+            endpoint: value.endpoint.to_string(),
+            state: value.state.as_str().to_string(),
+            failures: value.failures,
+            opened: value.opened_at.map(|opened_at| opened_at.to_rfc3339()),
+            retry_after: value.retry_after,
+        }
+    }
+}
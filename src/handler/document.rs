@@ -1 +1,2 @@
+pub mod guideline;
 pub mod thinktank;
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use actix_multipart::form::MultipartForm;
+use actix_web::http::StatusCode;
+use actix_web::web::{Data, Path, Query};
+use actix_web::Responder;
+use qdrant_client::Qdrant;
+use sqlx::PgPool;
+use tokio::sync::Mutex;
+
+use crate::blunder::document::DocumentError;
+use crate::configuration::common::CommonSettings;
+use crate::database::ingestion::JobKind;
+use crate::domain::request::document::guideline::{BatchUploadDomainRequest, UploadDomainRequest};
+use crate::dto::request::document::generally::JobsRequest;
+use crate::dto::request::document::guideline::{BatchUploadRequest, UploadRequest};
+use crate::dto::response::document::generally::{
+    BatchUploadResponse, DeleteResponse, DocumentResponse, JobResponse, JobsResponse,
+    UploadResponse,
+};
+use crate::dto::transformer::document::generally::uuid;
+use crate::service::document::{generally, guideline};
+use crate::worker::ingestion::{IngestionQueue, IngestionTask};
+
+#[tracing::instrument(
+    name = "Upload audit guideline document",
+    skip(form, pgpool, common, queue),
+    fields(
+        fileuuid=%form.uuid.as_str(),
+        filename=%form.name.as_str(),
+        authority=%form.authority.as_str(),
+    )
+)]
+pub async fn upload(
+    form: MultipartForm<UploadRequest>,
+    pgpool: Data<PgPool>,
+    common: Data<CommonSettings>,
+    queue: Data<IngestionQueue>,
+) -> Result<impl Responder, DocumentError> {
+    let domain: UploadDomainRequest = form
+        .into_inner()
+        .try_into()
+        .map_err(DocumentError::ValidationError)?;
+
+    // 先检查限制并占用队列位置再登记入库任务, 保证每一次登记的上传都会被处理
+    generally::ensure_limits(&domain.file, &common.limits).await?;
+    generally::ensure_idle(&pgpool, JobKind::Guideline, &domain.uuid.to_string()).await?;
+    let permit = queue.reserve()?;
+    let job = guideline::receive(domain, &pgpool, &common).await?;
+    permit.send(IngestionTask::new(job.id));
+
+    Ok(DocumentResponse::success(
+        StatusCode::ACCEPTED,
+        UploadResponse {
+            job: job.id.to_string(),
+            version: job.version,
+        },
+    ))
+}
+
+#[tracing::instrument(
+    name = "Batch upload audit guideline documents",
+    skip(form, pgpool, common, queue),
+    fields(files = form.files.len())
+)]
+pub async fn batch(
+    form: MultipartForm<BatchUploadRequest>,
+    pgpool: Data<PgPool>,
+    common: Data<CommonSettings>,
+    queue: Data<IngestionQueue>,
+) -> Result<impl Responder, DocumentError> {
+    let domain: BatchUploadDomainRequest = (form.into_inner(), common.as_ref())
+        .try_into()
+        .map_err(DocumentError::ValidationError)?;
+
+    let response = generally::batch(
+        domain.documents,
+        JobKind::Guideline,
+        &pgpool,
+        &common.limits,
+        &queue,
+        |document| guideline::receive(document, &pgpool, &common),
+    )
+    .await?;
+
+    let response = BatchUploadResponse::from(response);
+    Ok(DocumentResponse::partial(
+        StatusCode::ACCEPTED,
+        response.is_complete(),
+        "部分文档登记失败",
+        response,
+    ))
+}
+
+#[tracing::instrument(name = "Query audit guideline document status", skip(pgpool))]
+pub async fn status(
+    path: Path<String>,
+    pgpool: Data<PgPool>,
+) -> Result<impl Responder, DocumentError> {
+    let response = generally::status(&pgpool, JobKind::Guideline, &path.into_inner()).await?;
+    Ok(DocumentResponse::success(
+        StatusCode::OK,
+        JobResponse::from(response),
+    ))
+}
+
+#[tracing::instrument(name = "Query audit guideline document jobs", skip(query, pgpool))]
+pub async fn jobs(
+    query: Query<JobsRequest>,
+    pgpool: Data<PgPool>,
+) -> Result<impl Responder, DocumentError> {
+    let domain = query
+        .into_inner()
+        .try_into()
+        .map_err(DocumentError::ValidationError)?;
+    let response = generally::jobs(domain, &pgpool, JobKind::Guideline).await?;
+    Ok(DocumentResponse::success(
+        StatusCode::OK,
+        JobsResponse::from(response),
+    ))
+}
+
+#[tracing::instrument(name = "Delete audit guideline document", skip(pgpool, qdrant, common))]
+pub async fn delete(
+    path: Path<String>,
+    pgpool: Data<PgPool>,
+    qdrant: Data<Arc<Mutex<Qdrant>>>,
+    common: Data<CommonSettings>,
+) -> Result<impl Responder, DocumentError> {
+    // 删除时会根据文档主键删除缓存目录, 先校验主键格式
+    let uuid = uuid(path.into_inner()).map_err(DocumentError::ValidationError)?;
+    let response = generally::delete(
+        &pgpool,
+        &qdrant,
+        JobKind::Guideline,
+        &common.guideline_collection,
+        &common.guideline_cache,
+        &uuid.to_string(),
+    )
+    .await?;
+
+    Ok(DocumentResponse::partial(
+        StatusCode::OK,
+        response.is_complete(),
+        "文档部分删除失败",
+        DeleteResponse::from(response),
+    ))
+}
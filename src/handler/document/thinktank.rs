@@ -1,22 +1,37 @@
 use std::sync::Arc;
 
 use actix_multipart::form::MultipartForm;
-use actix_web::web::Data;
+use actix_web::http::StatusCode;
+use actix_web::web::{Data, Json, Path, Query};
 use actix_web::Responder;
 use qdrant_client::Qdrant;
 use reqwest::Client;
+use sqlx::PgPool;
 use tokio::sync::Mutex;
-use tracing::Instrument;
 
 use crate::blunder::document::DocumentError;
 use crate::configuration::common::CommonSettings;
 use crate::configuration::itools::ItoolsSettings;
-use crate::dto::request::document::thinktank::UploadRequest;
-use crate::service::document::thinktank;
+use crate::database::ingestion::JobKind;
+use crate::domain::request::document::thinktank::{
+    BatchUploadDomainRequest, ImportDomainRequest, UploadDomainRequest,
+};
+use crate::dto::request::document::generally::JobsRequest;
+use crate::dto::request::document::thinktank::{
+    BatchUploadRequest, ImportRequest, SearchRequest, UploadRequest,
+};
+use crate::dto::response::document::generally::{
+    BatchUploadResponse, DeleteResponse, DocumentResponse, JobResponse, JobsResponse,
+    UploadResponse,
+};
+use crate::dto::response::document::thinktank::{ImportResponse, SearchResponse};
+use crate::dto::transformer::document::generally::uuid;
+use crate::service::document::{generally, thinktank};
+use crate::worker::ingestion::{IngestionQueue, IngestionTask};
 
 #[tracing::instrument(
     name = "Upload audit thinktank document",
-    skip(form, qdrant, itools, common, client),
+    skip(form, pgpool, common, queue),
     fields(
         fileuuid=%form.uuid.as_str(),
         filename=%form.name.as_str(),
@@ -25,29 +40,168 @@ use crate::service::document::thinktank;
 )]
 pub async fn upload(
     form: MultipartForm<UploadRequest>,
+    pgpool: Data<PgPool>,
+    common: Data<CommonSettings>,
+    queue: Data<IngestionQueue>,
+) -> Result<impl Responder, DocumentError> {
+    let domain: UploadDomainRequest = (form.into_inner(), &common.vocabulary)
+        .try_into()
+        .map_err(DocumentError::ValidationError)?;
+
+    // 先检查限制并占用队列位置再登记入库任务, 保证每一次登记的上传都会被处理
+    generally::ensure_limits(&domain.file, &common.limits).await?;
+    generally::ensure_idle(&pgpool, JobKind::Thinktank, &domain.uuid.to_string()).await?;
+    let permit = queue.reserve()?;
+    let job = thinktank::receive(domain, &pgpool, &common).await?;
+    permit.send(IngestionTask::new(job.id));
+
+    Ok(DocumentResponse::success(
+        StatusCode::ACCEPTED,
+        UploadResponse {
+            job: job.id.to_string(),
+            version: job.version,
+        },
+    ))
+}
+
+#[tracing::instrument(
+    name = "Search audit thinktank document",
+    skip(json, qdrant, itools, common, client),
+    fields(question=%json.question.as_str())
+)]
+pub async fn search(
+    json: Json<SearchRequest>,
     qdrant: Data<Arc<Mutex<Qdrant>>>,
     itools: Data<ItoolsSettings>,
     common: Data<CommonSettings>,
     client: Data<Client>,
 ) -> Result<impl Responder, DocumentError> {
-    let domain = form
+    let domain = json
         .into_inner()
         .try_into()
         .map_err(DocumentError::ValidationError)?;
 
-    tokio::spawn(
-        async move {
-            thinktank::upload(domain, &qdrant, &itools, &common, &client)
-                .await
-                .map_err(|error| {
-                    // error = %error, 只会记录最顶层错误
-                    // error = ?error, 会记录完整的错误链
-                    tracing::error!(error = ?error);
-                })
-        }
-        // 创建独立的任务上下文, 并将跨度传递给新任务, 这样可以让任务继承当前上下文的tracing信息
-        .instrument(tracing::info_span!("Upload audit thinktank document task")),
-    );
-
-    Ok("rust")
+    let response = thinktank::search(domain, &qdrant, &itools, &common, &client).await?;
+
+    Ok(DocumentResponse::success(
+        StatusCode::OK,
+        SearchResponse::from(response),
+    ))
+}
+
+#[tracing::instrument(
+    name = "Batch upload audit thinktank documents",
+    skip(form, pgpool, common, queue),
+    fields(files = form.files.len())
+)]
+pub async fn batch(
+    form: MultipartForm<BatchUploadRequest>,
+    pgpool: Data<PgPool>,
+    common: Data<CommonSettings>,
+    queue: Data<IngestionQueue>,
+) -> Result<impl Responder, DocumentError> {
+    let domain: BatchUploadDomainRequest = (form.into_inner(), common.as_ref())
+        .try_into()
+        .map_err(DocumentError::ValidationError)?;
+
+    let response = generally::batch(
+        domain.documents,
+        JobKind::Thinktank,
+        &pgpool,
+        &common.limits,
+        &queue,
+        |document| thinktank::receive(document, &pgpool, &common),
+    )
+    .await?;
+
+    let response = BatchUploadResponse::from(response);
+    Ok(DocumentResponse::partial(
+        StatusCode::ACCEPTED,
+        response.is_complete(),
+        "部分文档登记失败",
+        response,
+    ))
+}
+
+// 从服务器目录导入文档, 未全部登记时返回207, 再次调用同一清单会从上次的进度继续
+#[tracing::instrument(
+    name = "Import audit thinktank documents",
+    skip(json, pgpool, common, queue),
+    fields(directory=%json.directory.as_str(), manifest=%json.manifest.as_str())
+)]
+pub async fn import(
+    json: Json<ImportRequest>,
+    pgpool: Data<PgPool>,
+    common: Data<CommonSettings>,
+    queue: Data<IngestionQueue>,
+) -> Result<impl Responder, DocumentError> {
+    let domain = ImportDomainRequest::from(json.into_inner());
+    let response = thinktank::import(domain, &pgpool, &common, &queue).await?;
+
+    let response = ImportResponse::from(response);
+    let status = match response.dry_run {
+        true => StatusCode::OK,
+        false => StatusCode::ACCEPTED,
+    };
+    Ok(DocumentResponse::partial(
+        status,
+        response.is_complete(),
+        "部分文档导入失败",
+        response,
+    ))
+}
+
+#[tracing::instrument(name = "Query audit thinktank document status", skip(pgpool))]
+pub async fn status(
+    path: Path<String>,
+    pgpool: Data<PgPool>,
+) -> Result<impl Responder, DocumentError> {
+    let response = generally::status(&pgpool, JobKind::Thinktank, &path.into_inner()).await?;
+    Ok(DocumentResponse::success(
+        StatusCode::OK,
+        JobResponse::from(response),
+    ))
+}
+
+#[tracing::instrument(name = "Query audit thinktank document jobs", skip(query, pgpool))]
+pub async fn jobs(
+    query: Query<JobsRequest>,
+    pgpool: Data<PgPool>,
+) -> Result<impl Responder, DocumentError> {
+    let domain = query
+        .into_inner()
+        .try_into()
+        .map_err(DocumentError::ValidationError)?;
+    let response = generally::jobs(domain, &pgpool, JobKind::Thinktank).await?;
+    Ok(DocumentResponse::success(
+        StatusCode::OK,
+        JobsResponse::from(response),
+    ))
+}
+
+#[tracing::instrument(name = "Delete audit thinktank document", skip(pgpool, qdrant, common))]
+pub async fn delete(
+    path: Path<String>,
+    pgpool: Data<PgPool>,
+    qdrant: Data<Arc<Mutex<Qdrant>>>,
+    common: Data<CommonSettings>,
+) -> Result<impl Responder, DocumentError> {
+    // 删除时会根据文档主键删除缓存目录, 先校验主键格式
+    let uuid = uuid(path.into_inner()).map_err(DocumentError::ValidationError)?;
+    let response = generally::delete(
+        &pgpool,
+        &qdrant,
+        JobKind::Thinktank,
+        &common.thinktank_collection,
+        &common.thinktank_cache,
+        &uuid.to_string(),
+    )
+    .await?;
+
+    Ok(DocumentResponse::partial(
+        StatusCode::OK,
+        response.is_complete(),
+        "文档部分删除失败",
+        DeleteResponse::from(response),
+    ))
 }
@@ -0,0 +1,15 @@
+use actix_web::http::StatusCode;
+use actix_web::Responder;
+
+use crate::dto::response::document::generally::DocumentResponse;
+use crate::dto::response::itools::CircuitResponse;
+use crate::helper::proxy;
+
+#[tracing::instrument(name = "Query itools circuit breakers")]
+pub async fn circuits() -> impl Responder {
+    let response: Vec<CircuitResponse> = proxy::circuit_snapshots()
+        .into_iter()
+        .map(CircuitResponse::from)
+        .collect();
+    DocumentResponse::success(StatusCode::OK, response)
+}
@@ -1,2 +1,7 @@
 pub mod cipher;
+pub mod extractor;
+pub mod inspector;
 pub mod proxy;
+pub mod sniffer;
+pub mod splitter;
+pub mod vector;
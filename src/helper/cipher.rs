@@ -2,10 +2,94 @@ use murmurhash64::murmur_hash64a;
 
 const SEED: u64 = 2915580697;
 
-pub fn murmurhash64int<S: AsRef<str>>(s: S) -> u64 {
-    murmur_hash64a(s.as_ref().as_bytes(), SEED)
+pub fn murmurhash64int<S: AsRef<[u8]>>(s: S) -> u64 {
+    murmur_hash64a(s.as_ref(), SEED)
 }
 
-pub fn murmurhash64str<S: AsRef<str>>(s: S) -> String {
+pub fn murmurhash64str<S: AsRef<[u8]>>(s: S) -> String {
     murmurhash64int(s).to_string()
 }
+
+const MULTIPLIER: u64 = 0xc6a4a7935bd1e995;
+const SHIFT: u32 = 47;
+
+// 分块计算的MurmurHash64A, 结果与murmurhash64int一次性计算的结果相同,
+// 算法的初始值依赖数据总长度, 需要在开始时给出
+pub struct Murmurhash64 {
+    hash: u64,
+    block: [u8; 8],
+    filled: usize,
+}
+
+impl Murmurhash64 {
+    pub fn new(length: u64) -> Self {
+        Self {
+            hash: SEED ^ length.wrapping_mul(MULTIPLIER),
+            block: [0; 8],
+            filled: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        if self.filled > 0 {
+            let take = (8 - self.filled).min(data.len());
+            self.block[self.filled..self.filled + take].copy_from_slice(&data[..take]);
+            self.filled += take;
+            data = &data[take..];
+            if self.filled < 8 {
+                return;
+            }
+            self.mix(u64::from_le_bytes(self.block));
+            self.filled = 0;
+        }
+
+        let mut chunks = data.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut block = [0; 8];
+            block.copy_from_slice(chunk);
+            self.mix(u64::from_le_bytes(block));
+        }
+        let remainder = chunks.remainder();
+        self.block[..remainder.len()].copy_from_slice(remainder);
+        self.filled = remainder.len();
+    }
+
+    pub fn finish(mut self) -> u64 {
+        if self.filled > 0 {
+            self.block[self.filled..].fill(0);
+            self.hash ^= u64::from_le_bytes(self.block);
+            self.hash = self.hash.wrapping_mul(MULTIPLIER);
+        }
+        let mut hash = self.hash;
+        hash ^= hash >> SHIFT;
+        hash = hash.wrapping_mul(MULTIPLIER);
+        hash ^= hash >> SHIFT;
+        hash
+    }
+
+    fn mix(&mut self, mut block: u64) {
+        block = block.wrapping_mul(MULTIPLIER);
+        block ^= block >> SHIFT;
+        block = block.wrapping_mul(MULTIPLIER);
+        self.hash ^= block;
+        self.hash = self.hash.wrapping_mul(MULTIPLIER);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaming_hash_matches_oneshot() {
+        let content: Vec<u8> = (0..100u8).collect();
+        for length in [0, 7, 8, 61, 100] {
+            let content = &content[..length];
+            for size in [1, 3, 8, 13] {
+                let mut hasher = Murmurhash64::new(length as u64);
+                content.chunks(size).for_each(|chunk| hasher.update(chunk));
+                assert_eq!(hasher.finish(), murmurhash64int(content));
+            }
+        }
+    }
+}
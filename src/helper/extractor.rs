@@ -0,0 +1,44 @@
+use std::future::Future;
+use std::path::Path;
+
+use anyhow::Error;
+use serde_json::{Map, Value};
+
+pub mod csv;
+pub mod docx;
+pub mod html;
+pub mod itools;
+pub mod spreadsheet;
+pub mod text;
+
+// 从文档中提取文本内容, 表格文档提取为`{工作表名称: 行数组的JSON字符串}`格式,
+// 与itools表格读取服务的table模式保持一致
+pub trait Extractor {
+    fn extract(&self, filepath: &Path) -> impl Future<Output = Result<String, Error>> + Send;
+}
+
+// 表格的首行作为表头, 其余每一行转换为`{表头: 单元格}`对象, 空行不参与转换
+fn table_rows<I: IntoIterator<Item = Vec<String>>>(rows: I) -> Vec<Map<String, Value>> {
+    let mut rows = rows.into_iter();
+    let Some(headers) = rows.next() else {
+        return Vec::new();
+    };
+    // 表头为空时使用列序号代替, 保证每一列都有名称
+    let headers: Vec<String> = headers
+        .iter()
+        .enumerate()
+        .map(|(index, cell)| match cell.trim() {
+            "" => format!("列{}", index + 1),
+            header => header.to_string(),
+        })
+        .collect();
+    rows.filter(|row| row.iter().any(|cell| !cell.trim().is_empty()))
+        .map(|row| {
+            headers
+                .iter()
+                .zip(row)
+                .map(|(header, cell)| (header.clone(), Value::String(cell)))
+                .collect()
+        })
+        .collect()
+}
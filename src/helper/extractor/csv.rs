@@ -0,0 +1,57 @@
+use std::future::Future;
+use std::path::Path;
+
+use anyhow::{Context, Error};
+use serde_json::{Map, Value};
+
+use crate::helper::extractor::text::read_text;
+use crate::helper::extractor::{table_rows, Extractor};
+
+// 读取csv文档, 整个文档作为一个以文件名命名的工作表, 提取结果与表格文档格式相同
+pub struct CsvExtractor;
+
+impl Extractor for CsvExtractor {
+    fn extract(&self, filepath: &Path) -> impl Future<Output = Result<String, Error>> + Send {
+        let filepath = filepath.to_path_buf();
+        async move {
+            let content = read_text(&filepath).await?;
+            let sheet = filepath
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            csv_extract(&content, sheet)
+                .with_context(|| format!("Failed to parse csv of {:?}", filepath))
+        }
+    }
+}
+
+fn csv_extract(content: &str, sheet: String) -> Result<String, Error> {
+    // 各行的列数可以不同, 缺少的列不输出, 多出的列没有表头时忽略
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let records = reader
+        .records()
+        .map(|record| Ok(record?.iter().map(str::to_string).collect()))
+        .collect::<Result<Vec<Vec<String>>, csv::Error>>()?;
+    let rows = serde_json::to_string(&table_rows(records))?;
+    let mut sheets = Map::new();
+    sheets.insert(sheet, Value::String(rows));
+    Ok(serde_json::to_string(&sheets)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_csv_to_sheet() {
+        let content = "事项,金额\n差旅费,\"1,200\"\n,\n会议费\n";
+        let extracted = csv_extract(content, "费用".to_string()).unwrap();
+        assert_eq!(
+            extracted,
+            r#"{"费用":"[{\"事项\":\"差旅费\",\"金额\":\"1,200\"},{\"事项\":\"会议费\"}]"}"#
+        );
+    }
+}
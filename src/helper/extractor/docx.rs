@@ -0,0 +1,113 @@
+use std::fs::File;
+use std::future::Future;
+use std::path::Path;
+
+use anyhow::{Context, Error};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use zip::ZipArchive;
+
+use crate::helper::extractor::Extractor;
+use crate::helper::inspector;
+
+// 直接读取docx压缩包中的word/document.xml, 每个段落提取为一行文本,
+// 解压的大小不超过上传时检查的解压后大小上限
+pub struct DocxExtractor {
+    limit: u64,
+}
+
+impl DocxExtractor {
+    pub fn new(limit: u64) -> Self {
+        Self { limit }
+    }
+}
+
+impl Extractor for DocxExtractor {
+    fn extract(&self, filepath: &Path) -> impl Future<Output = Result<String, Error>> + Send {
+        let filepath = filepath.to_path_buf();
+        let limit = self.limit;
+        async move {
+            tokio::task::spawn_blocking(move || docx_extract(&filepath, limit))
+                .await
+                .context("Failed to join docx extractor")?
+        }
+    }
+}
+
+fn docx_extract(filepath: &Path, limit: u64) -> Result<String, Error> {
+    let file = File::open(filepath)
+        .with_context(|| format!("Failed to open document of {:?}", filepath))?;
+    let mut archive = ZipArchive::new(file)
+        .with_context(|| format!("Failed to read docx archive of {:?}", filepath))?;
+    let entry = archive
+        .by_name("word/document.xml")
+        .with_context(|| format!("Missing word/document.xml in {:?}", filepath))?;
+    let xml = inspector::read_entry(entry, limit)
+        .with_context(|| format!("Failed to read word/document.xml of {:?}", filepath))?;
+    document_text(&xml).with_context(|| format!("Failed to parse docx of {:?}", filepath))
+}
+
+fn document_text(xml: &str) -> Result<String, Error> {
+    let mut reader = Reader::from_str(xml);
+    let mut paragraphs = Vec::new();
+    let mut paragraph = String::new();
+    let mut in_text = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) if element.local_name().as_ref() == b"t" => in_text = true,
+            Event::End(element) => match element.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"p" => paragraphs.push(std::mem::take(&mut paragraph)),
+                _ => {}
+            },
+            Event::Empty(element) => match element.local_name().as_ref() {
+                b"tab" => paragraph.push('\t'),
+                b"br" | b"cr" => paragraph.push('\n'),
+                _ => {}
+            },
+            Event::Text(text) if in_text => paragraph.push_str(&text.unescape()?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    // 空段落只用于排版, 没有检索价值
+    Ok(paragraphs
+        .into_iter()
+        .filter(|paragraph| !paragraph.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    use super::*;
+
+    const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:body>
+    <w:p><w:r><w:t>第一条</w:t></w:r><w:r><w:tab/><w:t xml:space="preserve">为了规范 &amp; 审计</w:t></w:r></w:p>
+    <w:p></w:p>
+    <w:p><w:r><w:t>第二条</w:t></w:r></w:p>
+  </w:body>
+</w:document>"#;
+
+    #[test]
+    fn extract_paragraphs_from_docx() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = ZipWriter::new(file.reopen().unwrap());
+        writer
+            .start_file("word/document.xml", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(DOCUMENT.as_bytes()).unwrap();
+        writer.finish().unwrap();
+
+        let text = docx_extract(file.path(), 1 << 20).unwrap();
+        assert!(docx_extract(file.path(), 16).is_err());
+        assert_eq!(text, "第一条\t为了规范 & 审计\n第二条");
+    }
+}
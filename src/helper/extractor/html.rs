@@ -0,0 +1,192 @@
+use std::future::Future;
+use std::path::Path;
+
+use anyhow::{Context, Error};
+use encoding_rs::Encoding;
+use tokio::fs;
+
+use crate::helper::extractor::text::decode_text;
+use crate::helper::extractor::Extractor;
+
+// 读取保存的网页, 去掉标签、脚本和样式, 块级元素之间换行, 保留网页中的文字内容
+pub struct HtmlExtractor;
+
+impl Extractor for HtmlExtractor {
+    fn extract(&self, filepath: &Path) -> impl Future<Output = Result<String, Error>> + Send {
+        let filepath = filepath.to_path_buf();
+        async move {
+            let content = fs::read(&filepath)
+                .await
+                .with_context(|| format!("Failed to read document of {:?}", filepath))?;
+            Ok(html_text(&decode_text(&content, meta_charset(&content))))
+        }
+    }
+}
+
+// 网页在开头的meta元素中声明编码, 只在前1024个字节内查找, 与浏览器预扫描的范围一致
+fn meta_charset(content: &[u8]) -> Option<&'static Encoding> {
+    let head = content[..content.len().min(1024)].to_ascii_lowercase();
+    let index = head.windows(8).position(|window| window == b"charset=")?;
+    let label: Vec<u8> = head[index + 8..]
+        .iter()
+        .skip_while(|byte| matches!(byte, b'"' | b'\'' | b' '))
+        .take_while(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_'))
+        .copied()
+        .collect();
+    Encoding::for_label(&label)
+}
+
+// 内容不属于正文的元素, 整个元素连同内容一起跳过
+const SKIPPED_ELEMENTS: [&str; 5] = ["script", "style", "noscript", "template", "svg"];
+
+// 块级元素的开始和结束位置换行
+const BLOCK_ELEMENTS: [&str; 26] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "title",
+    "tr",
+];
+
+fn html_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..start]));
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if !closing && SKIPPED_ELEMENTS.contains(&name.as_str()) && !tag.ends_with('/') {
+            let close = format!("</{}", name);
+            rest = rest
+                .to_ascii_lowercase()
+                .find(&close)
+                .map_or("", |index| &rest[index..]);
+            continue;
+        }
+        if BLOCK_ELEMENTS.contains(&name.as_str()) {
+            text.push('\n');
+        } else if matches!(name.as_str(), "td" | "th") && !closing {
+            text.push(' ');
+        }
+    }
+    text.push_str(&decode_entities(rest));
+
+    // 合并行内的连续空白, 去掉空行
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// 解码常用的命名字符引用和数字字符引用, 无法识别的引用保留原文
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((entity_char(&rest[1..end + 1])?, end + 2)));
+        match entity {
+            Some((c, length)) => {
+                decoded.push(c);
+                rest = &rest[length..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn entity_char(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let number = entity.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_html_text() {
+        let html = r#"<html><head><title>审计通知</title><style>p { color: red; }</style>
+            <SCRIPT>var a = "<p>";</SCRIPT></head>
+            <body><!-- 导航 --><h1>关于&nbsp;开展审计的通知</h1>
+            <p>各单位:<br/>请于<b>5月&#32;1日</b>前报送&lt;材料&gt;。</p>
+            <table><tr><td>事项</td><td>金额</td></tr></table></body></html>"#;
+        assert_eq!(
+            html_text(html),
+            "审计通知\n关于 开展审计的通知\n各单位:\n请于5月 1日前报送<材料>。\n事项 金额"
+        );
+    }
+
+    #[test]
+    fn detect_meta_charset() {
+        let html =
+            br#"<html><head><META http-equiv="Content-Type" content="text/html; charset=GB2312">"#;
+        assert_eq!(meta_charset(html), Some(encoding_rs::GBK));
+        assert_eq!(
+            meta_charset(br#"<meta charset='utf-8'>"#),
+            Some(encoding_rs::UTF_8)
+        );
+        assert_eq!(meta_charset(b"<html></html>"), None);
+    }
+}
@@ -0,0 +1,35 @@
+use std::future::Future;
+use std::path::Path;
+
+use anyhow::Error;
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::configuration::itools::ProxyEndpoint;
+use crate::helper::extractor::Extractor;
+use crate::helper::proxy;
+
+// 通过itools代理服务提取文本, 请求参数在options的基础上补充文档路径
+pub struct ItoolsExtractor<'a> {
+    client: &'a Client,
+    proxy: ProxyEndpoint,
+    options: Value,
+}
+
+impl<'a> ItoolsExtractor<'a> {
+    pub fn new(client: &'a Client, proxy: ProxyEndpoint, options: Value) -> Self {
+        Self {
+            client,
+            proxy,
+            options,
+        }
+    }
+}
+
+impl Extractor for ItoolsExtractor<'_> {
+    fn extract(&self, filepath: &Path) -> impl Future<Output = Result<String, Error>> + Send {
+        let mut value = self.options.clone();
+        value["filepath"] = Value::from(filepath.to_string_lossy());
+        async move { Ok(proxy::document_extractor(self.client, &self.proxy, value).await?) }
+    }
+}
@@ -0,0 +1,68 @@
+use std::future::Future;
+use std::path::Path;
+
+use anyhow::{Context, Error};
+use calamine::{open_workbook_auto, Data, Range, Reader};
+use serde_json::{Map, Value};
+
+use crate::helper::extractor::{table_rows, Extractor};
+
+// 读取xls和xlsx文档, 每个工作表的首行作为表头, 其余每一行转换为`{表头: 单元格}`对象
+pub struct SpreadsheetExtractor;
+
+impl Extractor for SpreadsheetExtractor {
+    fn extract(&self, filepath: &Path) -> impl Future<Output = Result<String, Error>> + Send {
+        let filepath = filepath.to_path_buf();
+        async move {
+            tokio::task::spawn_blocking(move || spreadsheet_extract(&filepath))
+                .await
+                .context("Failed to join spreadsheet extractor")?
+        }
+    }
+}
+
+fn spreadsheet_extract(filepath: &Path) -> Result<String, Error> {
+    let mut workbook = open_workbook_auto(filepath)
+        .with_context(|| format!("Failed to open spreadsheet of {:?}", filepath))?;
+    let mut sheets = Map::new();
+    for name in workbook.sheet_names() {
+        let range = workbook
+            .worksheet_range(&name)
+            .with_context(|| format!("Failed to read sheet {} of {:?}", name, filepath))?;
+        let rows = serde_json::to_string(&sheet_rows(&range))?;
+        sheets.insert(name, Value::String(rows));
+    }
+    Ok(serde_json::to_string(&sheets)?)
+}
+
+fn sheet_rows(range: &Range<Data>) -> Vec<Map<String, Value>> {
+    table_rows(
+        range
+            .rows()
+            .map(|row| row.iter().map(|cell| cell.to_string()).collect()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_rows_with_headers() {
+        let mut range = Range::new((0, 0), (3, 2));
+        range.set_value((0, 0), Data::String("事项".into()));
+        range.set_value((0, 2), Data::String("金额".into()));
+        range.set_value((1, 0), Data::String("差旅费".into()));
+        range.set_value((1, 1), Data::String("超标".into()));
+        range.set_value((1, 2), Data::Float(1200.5));
+        range.set_value((3, 0), Data::String("会议费".into()));
+
+        let rows = sheet_rows(&range);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            serde_json::to_string(&rows[0]).unwrap(),
+            r#"{"事项":"差旅费","列2":"超标","金额":"1200.5"}"#
+        );
+        assert_eq!(rows[1]["事项"], "会议费");
+    }
+}
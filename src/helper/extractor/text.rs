@@ -0,0 +1,58 @@
+use std::future::Future;
+use std::path::Path;
+
+use anyhow::{Context, Error};
+use encoding_rs::{Encoding, GB18030, UTF_8};
+use tokio::fs;
+
+use crate::helper::extractor::Extractor;
+
+// 直接读取txt和markdown文档, markdown的标题行由切片时按章节识别, 这里保留原文
+pub struct TextExtractor;
+
+impl Extractor for TextExtractor {
+    fn extract(&self, filepath: &Path) -> impl Future<Output = Result<String, Error>> + Send {
+        let filepath = filepath.to_path_buf();
+        async move { read_text(&filepath).await }
+    }
+}
+
+// 读取文本并识别编码, 去掉字节顺序标记并统一换行符
+pub async fn read_text(filepath: &Path) -> Result<String, Error> {
+    let content = fs::read(filepath)
+        .await
+        .with_context(|| format!("Failed to read document of {:?}", filepath))?;
+    Ok(decode_text(&content, None))
+}
+
+// 依次按照字节顺序标记、文档声明的编码和UTF-8解码, 都不满足时按照GB18030解码,
+// GB18030兼容GBK和GB2312, 覆盖国内常见的非UTF-8文本
+pub fn decode_text(content: &[u8], declared: Option<&'static Encoding>) -> String {
+    let encoding = Encoding::for_bom(content)
+        .map(|(encoding, _)| encoding)
+        .or(declared)
+        .or_else(|| std::str::from_utf8(content).is_ok().then_some(UTF_8))
+        .unwrap_or(GB18030);
+    let (text, _, _) = encoding.decode(content);
+    text.replace("\r\n", "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_text_encodings() {
+        let content = ["\u{FEFF}".as_bytes(), "审计报告\r\n正文".as_bytes()].concat();
+        assert_eq!(decode_text(&content, None), "审计报告\n正文");
+
+        let content: Vec<u8> = [0xFEFF, 0x5BA1, 0x8BA1]
+            .into_iter()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(decode_text(&content, None), "审计");
+
+        assert_eq!(decode_text(&[0xC9, 0xF3, 0xBC, 0xC6], None), "审计");
+        assert_eq!(decode_text("审计".as_bytes(), Some(UTF_8)), "审计");
+    }
+}
@@ -0,0 +1,271 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::{ensure, Context, Error};
+use calamine::{open_workbook_auto, Reader as _};
+use flate2::read::ZlibDecoder;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use zip::ZipArchive;
+
+// 压缩包中全部文件实际解压后的总大小, 中央目录中记录的大小由上传者决定, 不能作为依据,
+// 解压总量超过上限后立即停止, 此时返回的大小大于上限
+pub fn decompressed_size<R: Read + Seek>(reader: R, limit: u64) -> Result<u64, Error> {
+    let mut archive = ZipArchive::new(reader).context("Failed to read zip archive")?;
+    let mut total: u64 = 0;
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index)?;
+        let remaining = limit.saturating_sub(total).saturating_add(1);
+        total += io::copy(&mut entry.take(remaining), &mut io::sink())?;
+        if total > limit {
+            break;
+        }
+    }
+    Ok(total)
+}
+
+// 读取压缩包中的一个文件, 解压后超过limit个字节时返回错误
+pub fn read_entry<R: Read>(entry: R, limit: u64) -> Result<String, Error> {
+    let mut content = Vec::new();
+    entry
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut content)?;
+    ensure!(
+        content.len() as u64 <= limit,
+        "Archive entry exceeds {} bytes",
+        limit
+    );
+    Ok(String::from_utf8(content)?)
+}
+
+// 读取docx和pptx文档属性中记录的页数或幻灯片数, 文档属性不存在时返回None
+pub fn ooxml_pages<R: Read + Seek>(
+    reader: R,
+    element: &str,
+    limit: u64,
+) -> Result<Option<u32>, Error> {
+    let mut archive = ZipArchive::new(reader).context("Failed to read zip archive")?;
+    let xml = match archive.by_name("docProps/app.xml") {
+        Ok(entry) => read_entry(entry, limit)?,
+        Err(_) => return Ok(None),
+    };
+    let mut reader = Reader::from_str(&xml);
+    let mut inside = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => inside = start.local_name().as_ref() == element.as_bytes(),
+            Event::Text(text) if inside => return Ok(text.unescape()?.trim().parse().ok()),
+            Event::End(_) => inside = false,
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+// 分块读取pdf的窗口大小, 相邻窗口保留一段重叠, 避免页面对象和对象流的标记被窗口边界截断
+const PDF_WINDOW: usize = 64 * 1024;
+const PDF_OVERLAP: usize = 1024;
+
+// 统计pdf中的页面对象数量, 包括未压缩的页面对象和压缩对象流中的页面对象,
+// 对象流解压的总量不超过limit个字节, 没有找到任何页面对象时返回None
+pub fn pdf_pages<R: Read + Seek>(mut reader: R, limit: u64) -> Result<Option<u32>, Error> {
+    let mut pages = 0;
+    let mut streams = Vec::new();
+    reader.seek(SeekFrom::Start(0))?;
+    scan(reader.by_ref(), &mut 0, |buffer, end, base| {
+        pages += page_objects(buffer, end);
+        streams.extend(
+            object_streams(buffer, end)
+                .into_iter()
+                .map(|offset| base + offset as u64),
+        );
+    })?;
+
+    // 对象流只支持最常见的FlateDecode压缩, 无法解压的对象流直接跳过
+    let mut total = 0;
+    for offset in streams {
+        reader.seek(SeekFrom::Start(offset))?;
+        let remaining = limit.saturating_sub(total).saturating_add(1);
+        let decoder = ZlibDecoder::new(reader.by_ref()).take(remaining);
+        let _ = scan(decoder, &mut total, |buffer, end, _| {
+            pages += page_objects(buffer, end)
+        });
+        ensure!(
+            total <= limit,
+            "Object streams of pdf exceed {} bytes",
+            limit
+        );
+    }
+    Ok((pages > 0).then_some(pages))
+}
+
+// 按窗口读取全部内容, 每个窗口只处理起始位置在重叠区之前的标记, 重叠区留给下一个窗口,
+// 回调参数依次为窗口内容、处理的结束位置和窗口在全部内容中的起始位置
+fn scan<R: Read>(
+    mut reader: R,
+    total: &mut u64,
+    mut visit: impl FnMut(&[u8], usize, u64),
+) -> io::Result<()> {
+    let mut base = 0;
+    let mut buffer = Vec::with_capacity(PDF_WINDOW + PDF_OVERLAP);
+    loop {
+        let read = reader
+            .by_ref()
+            .take(PDF_WINDOW as u64)
+            .read_to_end(&mut buffer)?;
+        *total += read as u64;
+        let eof = read < PDF_WINDOW;
+        let end = if eof {
+            buffer.len()
+        } else {
+            buffer.len() - PDF_OVERLAP
+        };
+        visit(&buffer, end, base);
+        if eof {
+            return Ok(());
+        }
+        buffer.drain(..end);
+        base += end as u64;
+    }
+}
+
+fn page_objects(content: &[u8], end: usize) -> u32 {
+    let mut pages = 0;
+    let mut offset = 0;
+    while let Some(index) = find(&content[offset..], b"/Type") {
+        let start = offset + index;
+        if start >= end {
+            break;
+        }
+        offset = start + 5;
+        let value = content[offset..].trim_ascii_start();
+        if let Some(value) = value.strip_prefix(b"/Page") {
+            // 排除页面树节点/Pages
+            if !value.first().is_some_and(u8::is_ascii_alphanumeric) {
+                pages += 1;
+            }
+        }
+    }
+    pages
+}
+
+// 对象流字典之后的stream关键字和换行符之后是压缩的数据, 返回数据在窗口中的起始位置
+fn object_streams(content: &[u8], end: usize) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut offset = 0;
+    while let Some(index) = find(&content[offset..], b"/ObjStm") {
+        let start = offset + index;
+        if start >= end {
+            break;
+        }
+        offset = start + 7;
+        let dictionary = &content[offset..content.len().min(start + PDF_OVERLAP)];
+        if let Some(position) = find(dictionary, b"stream") {
+            let mut data = offset + position + 6;
+            if content.get(data) == Some(&b'\r') {
+                data += 1;
+            }
+            if content.get(data) == Some(&b'\n') {
+                data += 1;
+            }
+            offsets.push(data);
+        }
+    }
+    offsets
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+pub fn pdf_file_pages(filepath: &Path, limit: u64) -> Result<Option<u32>, Error> {
+    let file =
+        File::open(filepath).with_context(|| format!("Failed to open pdf of {:?}", filepath))?;
+    pdf_pages(BufReader::new(file), limit)
+        .with_context(|| format!("Failed to read pdf of {:?}", filepath))
+}
+
+pub fn workbook_sheets(filepath: &Path) -> Result<usize, Error> {
+    let workbook = open_workbook_auto(filepath)
+        .with_context(|| format!("Failed to open spreadsheet of {:?}", filepath))?;
+    Ok(workbook.sheet_names().len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    use super::*;
+
+    #[test]
+    fn count_pdf_pages() {
+        let pages = |content: &[u8]| pdf_pages(Cursor::new(content), 1 << 20).unwrap();
+        let content = b"1 0 obj << /Type /Pages /Count 2 >> endobj
+            2 0 obj << /Type /Page /Parent 1 0 R >> endobj
+            3 0 obj <</Type/Page/Parent 1 0 R>> endobj";
+        assert_eq!(pages(content), Some(2));
+        assert_eq!(pages(b"1 0 obj << /Type /Catalog >> endobj"), None);
+
+        // 页面对象的标记跨越读取窗口的边界时只统计一次
+        for padding in PDF_WINDOW - 12..PDF_WINDOW + 2 {
+            let mut content = vec![b' '; padding];
+            content.extend_from_slice(b"<< /Type /Page >> << /Type /Pages >>");
+            assert_eq!(pages(&content), Some(1));
+        }
+    }
+
+    #[test]
+    fn count_pdf_pages_in_object_streams() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(b"2 0 3 40 <</Type/Page/Parent 1 0 R>> <</Type/Page/Parent 1 0 R>>")
+            .unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut content = b"%PDF-1.7\n4 0 obj\n<< /Type /ObjStm /N 2 /First 8 /Filter /FlateDecode >>\nstream\r\n".to_vec();
+        content.extend_from_slice(&compressed);
+        content.extend_from_slice(b"\nendstream\nendobj\n");
+
+        assert_eq!(pdf_pages(Cursor::new(&content), 1 << 20).unwrap(), Some(2));
+        assert!(pdf_pages(Cursor::new(&content), 16).is_err());
+    }
+
+    #[test]
+    fn inspect_ooxml_archive() {
+        let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+        archive
+            .start_file("docProps/app.xml", SimpleFileOptions::default())
+            .unwrap();
+        archive
+            .write_all(b"<Properties><Template>Normal</Template><Pages>12</Pages></Properties>")
+            .unwrap();
+        archive
+            .start_file("word/document.xml", SimpleFileOptions::default())
+            .unwrap();
+        archive.write_all(&[b' '; 4096]).unwrap();
+        let content = archive.finish().unwrap().into_inner();
+
+        assert_eq!(
+            decompressed_size(Cursor::new(&content), 1 << 20).unwrap(),
+            4096 + 69
+        );
+        // 超过上限时停止解压, 返回的大小刚好超过上限
+        assert_eq!(decompressed_size(Cursor::new(&content), 100).unwrap(), 101);
+        assert_eq!(
+            ooxml_pages(Cursor::new(&content), "Pages", 1 << 20).unwrap(),
+            Some(12)
+        );
+        assert_eq!(
+            ooxml_pages(Cursor::new(&content), "Slides", 1 << 20).unwrap(),
+            None
+        );
+        assert!(ooxml_pages(Cursor::new(&content), "Pages", 16).is_err());
+    }
+}
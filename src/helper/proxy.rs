@@ -1,32 +1,308 @@
-use anyhow::{ensure, Context, Error};
-use reqwest::{Client, Response};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde_json::Value;
+use tracing::Instrument;
+
+use crate::blunder::proxy::{body_snippet, ProxyError};
+use crate::configuration::itools::{CircuitSettings, ProxyEndpoint, RetrySettings};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+}
+
+// 单个代理服务的熔断器: 连续失败达到阈值后打开, 打开期间直接拒绝请求,
+// 超过熔断时长后进入半开状态并放行一个探测请求, 探测成功则关闭, 失败则重新打开
+struct CircuitBreaker {
+    settings: CircuitSettings,
+    state: CircuitState,
+    failures: u32,
+    opened_at: Option<DateTime<Utc>>,
+}
+
+impl CircuitBreaker {
+    fn new(settings: CircuitSettings) -> Self {
+        Self {
+            settings,
+            state: CircuitState::Closed,
+            failures: 0,
+            opened_at: None,
+        }
+    }
+
+    fn remaining(&self, now: DateTime<Utc>) -> u64 {
+        let elapsed = self
+            .opened_at
+            .map(|opened_at| (now - opened_at).num_seconds().max(0) as u64)
+            .unwrap_or(0);
+        self.settings.open_timeout.saturating_sub(elapsed)
+    }
+
+    // 半开状态下探测请求没有返回结果(例如任务被取消)时, 超过熔断时长后允许再次探测
+    fn acquire(&mut self, now: DateTime<Utc>) -> Result<(), u64> {
+        match self.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open | CircuitState::HalfOpen => match self.remaining(now) {
+                0 => {
+                    self.state = CircuitState::HalfOpen;
+                    self.opened_at = Some(now);
+                    Ok(())
+                }
+                remaining => Err(remaining),
+            },
+        }
+    }
+
+    fn record(&mut self, success: bool, now: DateTime<Utc>) {
+        if success {
+            self.state = CircuitState::Closed;
+            self.failures = 0;
+            self.opened_at = None;
+            return;
+        }
+        self.failures += 1;
+        if self.state == CircuitState::HalfOpen || self.failures >= self.settings.failure_threshold
+        {
+            self.state = CircuitState::Open;
+            self.opened_at = Some(now);
+        }
+    }
+}
+
+static CIRCUIT_BREAKERS: Lazy<Mutex<HashMap<&'static str, CircuitBreaker>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn circuit_acquire(proxy: &ProxyEndpoint) -> Result<(), ProxyError> {
+    let mut breakers = CIRCUIT_BREAKERS.lock().unwrap();
+    breakers
+        .entry(proxy.name)
+        .or_insert_with(|| CircuitBreaker::new(proxy.circuit))
+        .acquire(Utc::now())
+        .map_err(|retry_after| ProxyError::CircuitOpen {
+            endpoint: proxy.name,
+            retry_after,
+        })
+}
+
+fn circuit_record(proxy: &ProxyEndpoint, success: bool) {
+    let mut breakers = CIRCUIT_BREAKERS.lock().unwrap();
+    let breaker = breakers
+        .entry(proxy.name)
+        .or_insert_with(|| CircuitBreaker::new(proxy.circuit));
+    let previous = breaker.state;
+    breaker.record(success, Utc::now());
+    if breaker.state != previous {
+        tracing::warn!(
+            endpoint = proxy.name,
+            state = breaker.state.as_str(),
+            failures = breaker.failures,
+            "Circuit breaker state changed"
+        );
+    }
+}
+
+pub struct CircuitSnapshot {
+    pub endpoint: &'static str,
+    pub state: CircuitState,
+    pub failures: u32,
+    pub opened_at: Option<DateTime<Utc>>,
+    pub retry_after: u64,
+}
+
+// 已经调用过的代理服务的熔断状态, 按服务名称排序
+pub fn circuit_snapshots() -> Vec<CircuitSnapshot> {
+    let now = Utc::now();
+    let breakers = CIRCUIT_BREAKERS.lock().unwrap();
+    let mut snapshots: Vec<CircuitSnapshot> = breakers
+        .iter()
+        .map(|(endpoint, breaker)| CircuitSnapshot {
+            endpoint,
+            state: breaker.state,
+            failures: breaker.failures,
+            opened_at: breaker.opened_at,
+            retry_after: match breaker.state {
+                CircuitState::Closed => 0,
+                _ => breaker.remaining(now),
+            },
+        })
+        .collect();
+    snapshots.sort_by_key(|snapshot| snapshot.endpoint);
+    snapshots
+}
+
+// 代理服务成功返回的响应内容以及请求耗时
+struct ProxyReply {
+    body: String,
+    elapsed: u64,
+}
+
+// 熔断器打开时直接失败, 否则发送请求并根据最终结果更新熔断器
+#[tracing::instrument(name = "Call itools proxy", skip(client, value), fields(endpoint = proxy.name))]
+async fn request_handler(
+    client: &Client,
+    proxy: &ProxyEndpoint,
+    value: Value,
+) -> Result<ProxyReply, ProxyError> {
+    circuit_acquire(proxy)?;
+    let result = request_retrying(client, proxy, value).await;
+    let success = match &result {
+        Ok(_) => true,
+        Err(error) => !error.is_outage(),
+    };
+    circuit_record(proxy, success);
+    result
+}
+
+// 按照代理服务的重试策略发送请求, 可重试的失败会在退避后重试, 其他失败直接返回
+async fn request_retrying(
+    client: &Client,
+    proxy: &ProxyEndpoint,
+    value: Value,
+) -> Result<ProxyReply, ProxyError> {
+    let retry = &proxy.retry;
+    let mut attempt = 1;
+    loop {
+        let error = match request_once(client, proxy, &value)
+            .instrument(tracing::info_span!("Proxy attempt", attempt))
+            .await
+        {
+            Ok(reply) => return Ok(reply),
+            Err(error) => error,
+        };
+        if !error.is_retryable(retry) || attempt >= retry.max_attempts {
+            return Err(error);
+        }
+
+        let backoff = retry_backoff(retry, attempt);
+        tracing::warn!(
+            attempt,
+            kind = error.kind(),
+            status = ?error.status(),
+            error = %error,
+            "Retry proxy request after {:?}",
+            backoff
+        );
+        tokio::time::sleep(backoff).await;
+        attempt += 1;
+    }
+}
+
+async fn request_once(
+    client: &Client,
+    proxy: &ProxyEndpoint,
+    value: &Value,
+) -> Result<ProxyReply, ProxyError> {
+    let endpoint = proxy.name;
+    let start = Instant::now();
+    let elapsed = || start.elapsed().as_millis() as u64;
+    let classify = |source: reqwest::Error| {
+        let elapsed = elapsed();
+        if source.is_timeout() {
+            ProxyError::Timeout {
+                endpoint,
+                elapsed,
+                source,
+            }
+        } else if source.is_connect() {
+            ProxyError::Connect {
+                endpoint,
+                elapsed,
+                source,
+            }
+        } else {
+            ProxyError::Request {
+                endpoint,
+                elapsed,
+                source,
+            }
+        }
+    };
 
-async fn request_handler(client: &Client, proxy: &str, value: Value) -> Result<Response, Error> {
     let response = client
-        .post(proxy)
-        .json(&value)
+        .post(proxy.url.as_str())
+        .json(value)
         .send()
         .await
-        .with_context(|| format!("Failed to call proxy of {}", proxy))?;
-    Ok(response)
-}
-
-fn response_status_is_success(response: &Response) -> Result<(), Error> {
-    ensure!(
-        response.status().is_success(),
-        "Failed to fetch {}, status: {}, headers: {:?}",
-        response.url(),
-        response.status(),
-        response.headers(),
-    );
-    Ok(())
+        .map_err(classify)?;
+    let status = response.status();
+    let body = response.text().await.map_err(classify)?;
+    let elapsed = elapsed();
+
+    if status.is_success() {
+        return Ok(ProxyReply { body, elapsed });
+    }
+    let body = body_snippet(&body);
+    let status = status.as_u16();
+    if (400..500).contains(&status) {
+        Err(ProxyError::ClientStatus {
+            endpoint,
+            status,
+            body,
+            elapsed,
+        })
+    } else {
+        Err(ProxyError::ServerStatus {
+            endpoint,
+            status,
+            body,
+            elapsed,
+        })
+    }
+}
+
+fn response_decode<T: DeserializeOwned>(
+    proxy: &ProxyEndpoint,
+    reply: ProxyReply,
+) -> Result<T, ProxyError> {
+    serde_json::from_str(&reply.body).map_err(|source| ProxyError::Decode {
+        endpoint: proxy.name,
+        body: body_snippet(&reply.body),
+        elapsed: reply.elapsed,
+        source,
+    })
 }
 
-pub async fn document_convertor(client: &Client, proxy: &str, value: Value) -> Result<(), Error> {
-    let response = request_handler(client, proxy, value).await?;
-    response_status_is_success(&response)?;
+// 第n次失败后的退避时间为initial_backoff * 2^(n-1), 不超过max_backoff,
+// 开启抖动时在[0, 退避时间]内随机取值, 避免多个任务同时重试
+fn retry_backoff(retry: &RetrySettings, attempt: u32) -> Duration {
+    let backoff = retry
+        .initial_backoff
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(retry.max_backoff);
+    let backoff = if retry.jitter {
+        rand::thread_rng().gen_range(0..=backoff)
+    } else {
+        backoff
+    };
+    Duration::from_millis(backoff)
+}
+
+pub async fn document_convertor(
+    client: &Client,
+    proxy: &ProxyEndpoint,
+    value: Value,
+) -> Result<(), ProxyError> {
+    request_handler(client, proxy, value).await?;
     Ok(())
 }
 
@@ -37,15 +313,11 @@ struct DocumentExtractor {
 
 pub async fn document_extractor(
     client: &Client,
-    proxy: &str,
+    proxy: &ProxyEndpoint,
     value: Value,
-) -> Result<String, Error> {
-    let response = request_handler(client, proxy, value).await?;
-    response_status_is_success(&response)?;
-    let document_extractor = response
-        .json::<DocumentExtractor>()
-        .await
-        .with_context(|| format!("Failed to deserialize response body of {}", proxy))?;
+) -> Result<String, ProxyError> {
+    let reply = request_handler(client, proxy, value).await?;
+    let document_extractor: DocumentExtractor = response_decode(proxy, reply)?;
     Ok(document_extractor.content)
 }
 
@@ -56,15 +328,11 @@ struct DocumentEmbedding {
 
 pub async fn document_embedding(
     client: &Client,
-    proxy: &str,
+    proxy: &ProxyEndpoint,
     value: Value,
-) -> Result<Vec<f32>, Error> {
-    let response = request_handler(client, proxy, value).await?;
-    response_status_is_success(&response)?;
-    let document_embedding = response
-        .json::<DocumentEmbedding>()
-        .await
-        .with_context(|| format!("Failed to deserialize response body of {}", proxy))?;
+) -> Result<Vec<f32>, ProxyError> {
+    let reply = request_handler(client, proxy, value).await?;
+    let document_embedding: DocumentEmbedding = response_decode(proxy, reply)?;
     Ok(document_embedding.vector)
 }
 
@@ -75,15 +343,11 @@ struct DocumentReranking {
 
 pub async fn document_reranking(
     client: &Client,
-    proxy: &str,
+    proxy: &ProxyEndpoint,
     value: Value,
-) -> Result<Vec<f32>, Error> {
-    let response = request_handler(client, proxy, value).await?;
-    response_status_is_success(&response)?;
-    let document_reranking = response
-        .json::<DocumentReranking>()
-        .await
-        .with_context(|| format!("Failed to deserialize response body of {}", proxy))?;
+) -> Result<Vec<f32>, ProxyError> {
+    let reply = request_handler(client, proxy, value).await?;
+    let document_reranking: DocumentReranking = response_decode(proxy, reply)?;
     Ok(document_reranking.scores)
 }
 
@@ -94,14 +358,74 @@ struct DocumentSplitting {
 
 pub async fn document_splitting(
     client: &Client,
-    proxy: &str,
+    proxy: &ProxyEndpoint,
     value: Value,
-) -> Result<Vec<String>, Error> {
-    let response = request_handler(client, proxy, value).await?;
-    response_status_is_success(&response)?;
-    let document_splitting = response
-        .json::<DocumentSplitting>()
-        .await
-        .with_context(|| format!("Failed to deserialize response body of {}", proxy))?;
+) -> Result<Vec<String>, ProxyError> {
+    let reply = request_handler(client, proxy, value).await?;
+    let document_splitting: DocumentSplitting = response_decode(proxy, reply)?;
     Ok(document_splitting.slices)
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    #[test]
+    fn circuit_opens_and_half_opens() {
+        let start = Utc::now();
+        let mut breaker = CircuitBreaker::new(CircuitSettings {
+            failure_threshold: 2,
+            open_timeout: 30,
+        });
+
+        breaker.record(false, start);
+        assert_eq!(breaker.state, CircuitState::Closed);
+        breaker.record(false, start);
+        assert_eq!(breaker.state, CircuitState::Open);
+        assert_eq!(breaker.acquire(start + TimeDelta::seconds(10)), Err(20));
+
+        // 熔断时长过后只放行一个探测请求
+        let probe = start + TimeDelta::seconds(30);
+        assert_eq!(breaker.acquire(probe), Ok(()));
+        assert_eq!(breaker.state, CircuitState::HalfOpen);
+        assert_eq!(breaker.acquire(probe), Err(30));
+
+        // 探测失败重新打开, 探测成功则关闭
+        breaker.record(false, probe);
+        assert_eq!(breaker.state, CircuitState::Open);
+        let probe = probe + TimeDelta::seconds(30);
+        assert_eq!(breaker.acquire(probe), Ok(()));
+        breaker.record(true, probe);
+        assert_eq!(breaker.state, CircuitState::Closed);
+        assert_eq!(breaker.failures, 0);
+    }
+
+    fn retry_settings(jitter: bool) -> RetrySettings {
+        RetrySettings {
+            max_attempts: 5,
+            initial_backoff: 100,
+            max_backoff: 1000,
+            jitter,
+            retryable_statuses: vec![502, 503, 504],
+        }
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_until_limit() {
+        let retry = retry_settings(false);
+        let backoffs: Vec<u64> = (1..=6)
+            .map(|attempt| retry_backoff(&retry, attempt).as_millis() as u64)
+            .collect();
+        assert_eq!(backoffs, vec![100, 200, 400, 800, 1000, 1000]);
+    }
+
+    #[test]
+    fn jitter_never_exceeds_backoff() {
+        let retry = retry_settings(true);
+        for attempt in 1..=6 {
+            assert!(retry_backoff(&retry, attempt) <= Duration::from_millis(1000));
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use encoding_rs::{DecoderResult, Encoding, GB18030};
+use zip::ZipArchive;
+
+// 根据文件内容识别的文档类型, 复合文档无法识别具体类型时为Ole, 压缩包无法识别具体类型时为Zip
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileType {
+    Doc,
+    Xls,
+    Ppt,
+    Ole,
+    Docx,
+    Xlsx,
+    Pptx,
+    Zip,
+    Pdf,
+    Text,
+    Unknown,
+}
+
+impl FileType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            FileType::Doc => "doc",
+            FileType::Xls => "xls",
+            FileType::Ppt => "ppt",
+            FileType::Ole => "ole2",
+            FileType::Docx => "docx",
+            FileType::Xlsx => "xlsx",
+            FileType::Pptx => "pptx",
+            FileType::Zip => "zip",
+            FileType::Pdf => "pdf",
+            FileType::Text => "text",
+            FileType::Unknown => "unknown",
+        }
+    }
+}
+
+const OLE_SIGNATURE: &[u8] = b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1";
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+const PDF_SIGNATURE: &[u8] = b"%PDF-";
+// 读取文件开头用于识别类型的字节数, pdf的文件头允许出现在前1024个字节内
+const SNIFF_LENGTH: u64 = 8192;
+const PDF_OFFSET: usize = 1024;
+// [Content_Types].xml只声明各个部件的类型, 读取的长度足够识别主文档部件, 同时避免解压异常大的文件
+const CONTENT_TYPES_LENGTH: u64 = 1 << 20;
+
+// 依次识别复合文档、压缩包和pdf的文件头, 都不匹配时判断是否为文本
+pub fn sniff<R: Read + Seek>(reader: &mut R) -> io::Result<FileType> {
+    reader.seek(SeekFrom::Start(0))?;
+    let mut head = Vec::new();
+    reader.by_ref().take(SNIFF_LENGTH).read_to_end(&mut head)?;
+
+    let detected = if head.starts_with(OLE_SIGNATURE) {
+        ole_type(reader, &head)?
+    } else if head.starts_with(ZIP_SIGNATURE) {
+        zip_type(reader)
+    } else if head[..head.len().min(PDF_OFFSET)]
+        .windows(PDF_SIGNATURE.len())
+        .any(|window| window == PDF_SIGNATURE)
+    {
+        FileType::Pdf
+    } else if is_text(&head) {
+        FileType::Text
+    } else {
+        FileType::Unknown
+    };
+    reader.seek(SeekFrom::Start(0))?;
+    Ok(detected)
+}
+
+// 复合文档的第一个目录扇区中包含主要数据流的名称, 据此区分doc、xls和ppt
+fn ole_type<R: Read + Seek>(reader: &mut R, head: &[u8]) -> io::Result<FileType> {
+    if head.len() < 0x34 {
+        return Ok(FileType::Ole);
+    }
+    let shift = u16::from_le_bytes([head[0x1E], head[0x1F]]);
+    let sector = u32::from_le_bytes([head[0x30], head[0x31], head[0x32], head[0x33]]);
+    if !(7..=12).contains(&shift) {
+        return Ok(FileType::Ole);
+    }
+    let size = 1u64 << shift;
+    reader.seek(SeekFrom::Start((u64::from(sector) + 1) * size))?;
+    let mut directory = Vec::new();
+    reader.by_ref().take(size).read_to_end(&mut directory)?;
+
+    for entry in directory.chunks_exact(128) {
+        let length = usize::from(u16::from_le_bytes([entry[0x40], entry[0x41]])).min(64);
+        let units: Vec<u16> = entry[..length]
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .take_while(|unit| *unit != 0)
+            .collect();
+        match String::from_utf16_lossy(&units).as_str() {
+            "WordDocument" => return Ok(FileType::Doc),
+            "Workbook" | "Book" => return Ok(FileType::Xls),
+            "PowerPoint Document" => return Ok(FileType::Ppt),
+            _ => {}
+        }
+    }
+    Ok(FileType::Ole)
+}
+
+// office文档的[Content_Types].xml中声明了主文档部件的类型
+fn zip_type<R: Read + Seek>(reader: &mut R) -> FileType {
+    let content_types = ZipArchive::new(reader).ok().and_then(|mut archive| {
+        let mut content = Vec::new();
+        archive
+            .by_name("[Content_Types].xml")
+            .ok()?
+            .take(CONTENT_TYPES_LENGTH)
+            .read_to_end(&mut content)
+            .ok()?;
+        Some(String::from_utf8_lossy(&content).into_owned())
+    });
+    match content_types {
+        Some(content) if content.contains("wordprocessingml.document.main") => FileType::Docx,
+        Some(content) if content.contains("spreadsheetml.sheet.main") => FileType::Xlsx,
+        Some(content) if content.contains("presentationml.presentation.main") => FileType::Pptx,
+        _ => FileType::Zip,
+    }
+}
+
+// 带有字节顺序标记的文本直接认为是文本, UTF-16文本中会出现空字符;
+// 其他文本中不应出现空字符, 截断位置可能落在多字节字符中间, 只要求之前的内容是有效的UTF-8或GB18030
+fn is_text(head: &[u8]) -> bool {
+    if Encoding::for_bom(head).is_some() {
+        return true;
+    }
+    if head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(error) if error.error_len().is_none() => true,
+        Err(_) => is_gb18030(head),
+    }
+}
+
+fn is_gb18030(head: &[u8]) -> bool {
+    let mut decoder = GB18030.new_decoder_without_bom_handling();
+    let Some(length) = decoder.max_utf8_buffer_length_without_replacement(head.len()) else {
+        return false;
+    };
+    let mut output = vec![0; length];
+    let (result, _, _) = decoder.decode_to_utf8_without_replacement(head, &mut output, false);
+    result == DecoderResult::InputEmpty
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    use super::*;
+
+    fn ole_document(stream: &str) -> Vec<u8> {
+        let mut document = vec![0u8; 1024];
+        document[..8].copy_from_slice(OLE_SIGNATURE);
+        document[0x1E] = 9;
+        let entry = &mut document[512 + 128..512 + 256];
+        let name: Vec<u8> = stream
+            .encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        entry[..name.len()].copy_from_slice(&name);
+        entry[0x40..0x42].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        document
+    }
+
+    #[test]
+    fn sniff_file_types() {
+        let detect = |content: Vec<u8>| sniff(&mut Cursor::new(content)).unwrap();
+        assert_eq!(detect(b"%PDF-1.7\n".to_vec()), FileType::Pdf);
+        assert_eq!(detect(ole_document("WordDocument")), FileType::Doc);
+        assert_eq!(detect(ole_document("Workbook")), FileType::Xls);
+        assert_eq!(detect(ole_document("Unknown")), FileType::Ole);
+        assert_eq!(
+            detect("审计报告,金额".as_bytes()[..8].to_vec()),
+            FileType::Text
+        );
+        assert_eq!(detect(vec![0xC9, 0xF3, 0xBC, 0xC6, 0xC9]), FileType::Text);
+        assert_eq!(detect(vec![0xFF, 0xFE, 0xA1, 0x5B]), FileType::Text);
+        assert_eq!(detect(vec![0x89, b'P', b'N', b'G', 0]), FileType::Unknown);
+
+        let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+        archive
+            .start_file("[Content_Types].xml", SimpleFileOptions::default())
+            .unwrap();
+        archive
+            .write_all(br#"<Override ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#)
+            .unwrap();
+        let content = archive.finish().unwrap().into_inner();
+        assert_eq!(detect(content), FileType::Xlsx);
+    }
+}
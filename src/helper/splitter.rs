@@ -0,0 +1,339 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::configuration::splitting::{SplittingSettings, SplittingStrategy};
+
+// 切片内容以及表格文档切片所在的工作表和包含的列名, 非表格文档的切片只有内容
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Slice {
+    pub text: String,
+    #[serde(default)]
+    pub sheet: Option<String>,
+    #[serde(default)]
+    pub headers: Vec<String>,
+}
+
+impl From<String> for Slice {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            ..Default::default()
+        }
+    }
+}
+
+// 句子结束的标点, 中文标点直接结束句子, 英文标点后面需要跟空白字符, 避免误切小数和缩写
+const SENTENCE_TERMINATORS: &str = "。！？；!?;";
+const ENGLISH_TERMINATORS: &str = ".";
+// 句末标点后面紧跟的引号和括号属于同一个句子
+const SENTENCE_CLOSERS: &str = "”’」』）)\"'";
+
+// 按照本地切片方式切分文本, 返回去除首尾空白后的非空切片
+pub fn split(text: &str, settings: &SplittingSettings) -> Vec<String> {
+    let size = settings.chunk_size.max(1);
+    let overlap = settings.chunk_overlap;
+    let chunks = match settings.strategy {
+        SplittingStrategy::Fixed => fixed_chunks(text, size, overlap),
+        SplittingStrategy::Sentence => pack(&sentences(text), size, overlap),
+        // 条款之间不重叠, 保证每个切片只属于一个章节或条款, 过长的章节再按句子切分
+        SplittingStrategy::Section => sections(text)
+            .into_iter()
+            .flat_map(|section| match section.chars().count() <= size {
+                true => vec![section.to_string()],
+                false => pack(&sentences(section), size, overlap),
+            })
+            .collect(),
+        // 远程切片服务由调用方处理, 这里不切分
+        SplittingStrategy::Itools => vec![text.to_string()],
+    };
+    chunks
+        .into_iter()
+        .map(|chunk| chunk.trim().to_string())
+        .filter(|chunk| !chunk.is_empty())
+        .collect()
+}
+
+// 解析表格文档的提取结果`{工作表名称: 行数组}`, 每rows行生成一个切片,
+// 切片内容为`列名: 单元格`形式, 空白单元格和空行不参与切片
+pub fn split_sheets(extracted: &str, rows: usize) -> Result<Vec<Slice>, serde_json::Error> {
+    let sheets: Map<String, Value> = serde_json::from_str(extracted)?;
+    let mut slices = Vec::new();
+    for (sheet, data) in sheets {
+        // itools和本地提取返回的行数组都是JSON字符串, 同时兼容直接返回数组的情况
+        let records: Vec<Map<String, Value>> = match data {
+            Value::String(data) => serde_json::from_str(&data)?,
+            data => serde_json::from_value(data)?,
+        };
+        for group in records.chunks(rows.max(1)) {
+            let mut headers: Vec<String> = Vec::new();
+            let mut lines = Vec::new();
+            for record in group {
+                let mut cells = Vec::new();
+                for (header, value) in record {
+                    let value = cell_text(value);
+                    if value.is_empty() {
+                        continue;
+                    }
+                    if !headers.contains(header) {
+                        headers.push(header.clone());
+                    }
+                    cells.push(format!("{}: {}", header, value));
+                }
+                if !cells.is_empty() {
+                    lines.push(cells.join("; "));
+                }
+            }
+            if lines.is_empty() {
+                continue;
+            }
+            slices.push(Slice {
+                text: format!("工作表: {}\n{}", sheet, lines.join("\n")),
+                sheet: Some(sheet.clone()),
+                headers,
+            });
+        }
+    }
+    Ok(slices)
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.trim().to_string(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+// 按固定字符数切分, 相邻切片重叠overlap个字符, 重叠长度不小于切片长度时不重叠
+fn fixed_chunks(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let step = if overlap < size { size - overlap } else { size };
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let end = (start + size).min(chars.len());
+        chunks.push(chars[start..end].iter().collect());
+        if end == chars.len() {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+// 切分句子, 保留句子之间的空白字符, 所有句子拼接后与原文相同
+fn sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let next = chars.peek().map(|(_, next)| *next);
+        let terminated = SENTENCE_TERMINATORS.contains(c)
+            || c == '\n'
+            || (ENGLISH_TERMINATORS.contains(c) && next.is_none_or(char::is_whitespace));
+        if !terminated {
+            continue;
+        }
+        let mut end = index + c.len_utf8();
+        while let Some(&(index, closer)) = chars.peek() {
+            if !SENTENCE_CLOSERS.contains(closer) {
+                break;
+            }
+            end = index + closer.len_utf8();
+            chars.next();
+        }
+        sentences.push(&text[start..end]);
+        start = end;
+    }
+    if start < text.len() {
+        sentences.push(&text[start..]);
+    }
+    sentences
+}
+
+// 将句子依次合并为不超过size个字符的切片, 新切片以上一个切片末尾不超过overlap个字符的句子开头,
+// 单个句子超过size个字符时按固定字符数切分
+fn pack(units: &[&str], size: usize, overlap: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut length = 0;
+    for unit in units {
+        let count = unit.chars().count();
+        if count > size {
+            if !current.is_empty() {
+                chunks.push(current.concat());
+                current.clear();
+                length = 0;
+            }
+            chunks.extend(fixed_chunks(unit, size, overlap));
+            continue;
+        }
+        if length + count > size && !current.is_empty() {
+            chunks.push(current.concat());
+            let mut kept = 0;
+            let mut carried = 0;
+            for unit in current.iter().rev() {
+                let carry = unit.chars().count();
+                if carried + carry > overlap || carried + carry + count > size {
+                    break;
+                }
+                carried += carry;
+                kept += 1;
+            }
+            current.drain(..current.len() - kept);
+            length = carried;
+        }
+        current.push(unit);
+        length += count;
+    }
+    if !current.is_empty() {
+        chunks.push(current.concat());
+    }
+    chunks
+}
+
+// 以标题行为界切分章节, 标题行属于其后的章节
+fn sections(text: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if is_heading(line) && offset > start {
+            sections.push(&text[start..offset]);
+            start = offset;
+        }
+        offset += line.len();
+    }
+    if start < text.len() {
+        sections.push(&text[start..]);
+    }
+    sections
+}
+
+// 识别常见的标题: 第一章、第二节、第三条、一、以及markdown标题
+fn is_heading(line: &str) -> bool {
+    let line = line.trim_start();
+    if line.starts_with('#') {
+        return true;
+    }
+    if let Some(rest) = line.strip_prefix('第') {
+        let number: String = rest.chars().take_while(|c| is_numeral(*c)).collect();
+        let rest = &rest[number.len()..];
+        return !number.is_empty()
+            && ["章", "节", "条", "编", "部分"]
+                .iter()
+                .any(|marker| rest.starts_with(marker));
+    }
+    let number: String = line
+        .chars()
+        .take_while(|c| "一二三四五六七八九十".contains(*c))
+        .collect();
+    !number.is_empty() && line[number.len()..].starts_with('、')
+}
+
+fn is_numeral(c: char) -> bool {
+    c.is_ascii_digit() || "零〇一二两三四五六七八九十百千".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(
+        strategy: SplittingStrategy,
+        chunk_size: usize,
+        chunk_overlap: usize,
+    ) -> SplittingSettings {
+        SplittingSettings {
+            strategy,
+            chunk_size,
+            chunk_overlap,
+            rows_per_slice: 1,
+        }
+    }
+
+    #[test]
+    fn split_fixed_with_overlap() {
+        let chunks = split(
+            "一二三四五六七八九十",
+            &settings(SplittingStrategy::Fixed, 4, 1),
+        );
+        assert_eq!(chunks, vec!["一二三四", "四五六七", "七八九十"]);
+    }
+
+    #[test]
+    fn split_chinese_sentences() {
+        assert_eq!(
+            sentences("审计发现问题。是否整改？“已整改！”金额1.5万元; done. next"),
+            vec![
+                "审计发现问题。",
+                "是否整改？",
+                "“已整改！”",
+                "金额1.5万元;",
+                " done.",
+                " next"
+            ]
+        );
+    }
+
+    #[test]
+    fn pack_sentences_with_overlap() {
+        let chunks = split(
+            "甲乙丙。丁戊己。庚辛壬。癸子丑。",
+            &settings(SplittingStrategy::Sentence, 8, 4),
+        );
+        assert_eq!(
+            chunks,
+            vec!["甲乙丙。丁戊己。", "丁戊己。庚辛壬。", "庚辛壬。癸子丑。"]
+        );
+    }
+
+    #[test]
+    fn split_by_clauses() {
+        let text =
+            "第一章 总则\n第一条 为了规范审计工作。\n第二条 本办法适用于审计机关。\n一、其他事项\n";
+        let chunks = split(text, &settings(SplittingStrategy::Section, 100, 10));
+        assert_eq!(
+            chunks,
+            vec![
+                "第一章 总则",
+                "第一条 为了规范审计工作。",
+                "第二条 本办法适用于审计机关。",
+                "一、其他事项"
+            ]
+        );
+    }
+
+    #[test]
+    fn split_sheet_rows() {
+        let extracted = serde_json::json!({
+            "检查清单": r#"[{"事项":"差旅费","标准":"500"},{"事项":"","标准":""},{"事项":"会议费","标准":300}]"#,
+            "说明": [{"备注":"无"}],
+        })
+        .to_string();
+
+        let slices = split_sheets(&extracted, 1).unwrap();
+        assert_eq!(slices.len(), 3);
+        assert_eq!(slices[0].text, "工作表: 检查清单\n事项: 差旅费; 标准: 500");
+        assert_eq!(slices[0].sheet.as_deref(), Some("检查清单"));
+        assert_eq!(slices[0].headers, vec!["事项", "标准"]);
+        assert_eq!(slices[1].text, "工作表: 检查清单\n事项: 会议费; 标准: 300");
+        assert_eq!(slices[2].sheet.as_deref(), Some("说明"));
+
+        let slices = split_sheets(&extracted, 3).unwrap();
+        assert_eq!(slices.len(), 2);
+        assert_eq!(
+            slices[0].text,
+            "工作表: 检查清单\n事项: 差旅费; 标准: 500\n事项: 会议费; 标准: 300"
+        );
+    }
+
+    #[test]
+    fn detect_headings() {
+        assert!(is_heading("第十二条 审计机关"));
+        assert!(is_heading("## 附则"));
+        assert!(is_heading("三、审计程序"));
+        assert!(!is_heading("第一批次审计"));
+        assert!(!is_heading("本条例所称"));
+    }
+}
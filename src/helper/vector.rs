@@ -0,0 +1,162 @@
+use anyhow::{Context, Error};
+use qdrant_client::qdrant::{
+    CreateCollectionBuilder, DeletePointsBuilder, Distance, Filter, PointStruct,
+    QueryPointsBuilder, RetrievedPoint, ScoredPoint, ScrollPointsBuilder, SetPayloadPointsBuilder,
+    UpsertPointsBuilder, VectorParamsBuilder,
+};
+use qdrant_client::{Payload, Qdrant};
+
+// 集合不存在时按照向量维度创建集合, 向量维度由嵌入模型决定, 因此使用首个向量的长度
+pub async fn ensure_collection(qdrant: &Qdrant, collection: &str, size: u64) -> Result<(), Error> {
+    let exists = qdrant
+        .collection_exists(collection)
+        .await
+        .with_context(|| format!("Failed to check collection of {}", collection))?;
+    if exists {
+        return Ok(());
+    }
+    qdrant
+        .create_collection(
+            CreateCollectionBuilder::new(collection)
+                .vectors_config(VectorParamsBuilder::new(size, Distance::Cosine)),
+        )
+        .await
+        .with_context(|| format!("Failed to create collection of {}", collection))?;
+    Ok(())
+}
+
+// 删除后重新创建集合, 集合中已有的向量全部丢失
+pub async fn recreate_collection(
+    qdrant: &Qdrant,
+    collection: &str,
+    size: u64,
+) -> Result<(), Error> {
+    let exists = qdrant
+        .collection_exists(collection)
+        .await
+        .with_context(|| format!("Failed to check collection of {}", collection))?;
+    if exists {
+        qdrant
+            .delete_collection(collection)
+            .await
+            .with_context(|| format!("Failed to delete collection of {}", collection))?;
+    }
+    ensure_collection(qdrant, collection, size).await
+}
+
+pub async fn upsert_points(
+    qdrant: &Qdrant,
+    collection: &str,
+    points: Vec<PointStruct>,
+) -> Result<(), Error> {
+    qdrant
+        .upsert_points(UpsertPointsBuilder::new(collection, points).wait(true))
+        .await
+        .with_context(|| format!("Failed to upsert points into {}", collection))?;
+    Ok(())
+}
+
+// 集合不存在时说明还没有文档入库, 直接返回空结果
+pub async fn query_points(
+    qdrant: &Qdrant,
+    collection: &str,
+    vector: Vec<f32>,
+    filter: Filter,
+    limit: u64,
+) -> Result<Vec<ScoredPoint>, Error> {
+    let exists = qdrant
+        .collection_exists(collection)
+        .await
+        .with_context(|| format!("Failed to check collection of {}", collection))?;
+    if !exists {
+        return Ok(Vec::new());
+    }
+    let response = qdrant
+        .query(
+            QueryPointsBuilder::new(collection)
+                .query(vector)
+                .filter(filter)
+                .limit(limit)
+                .with_payload(true),
+        )
+        .await
+        .with_context(|| format!("Failed to query points from {}", collection))?;
+    Ok(response.result)
+}
+
+// 集合不存在时没有需要删除的向量, 直接返回
+pub async fn delete_points(qdrant: &Qdrant, collection: &str, filter: Filter) -> Result<(), Error> {
+    let exists = qdrant
+        .collection_exists(collection)
+        .await
+        .with_context(|| format!("Failed to check collection of {}", collection))?;
+    if !exists {
+        return Ok(());
+    }
+    qdrant
+        .delete_points(
+            DeletePointsBuilder::new(collection)
+                .points(filter)
+                .wait(true),
+        )
+        .await
+        .with_context(|| format!("Failed to delete points from {}", collection))?;
+    Ok(())
+}
+
+pub async fn set_payload(
+    qdrant: &Qdrant,
+    collection: &str,
+    payload: Payload,
+    filter: Filter,
+) -> Result<(), Error> {
+    qdrant
+        .set_payload(
+            SetPayloadPointsBuilder::new(collection, payload)
+                .points_selector(filter)
+                .wait(true),
+        )
+        .await
+        .with_context(|| format!("Failed to set payload of points in {}", collection))?;
+    Ok(())
+}
+
+// 分页读取满足条件的全部点, 包括向量和负载
+pub async fn scroll_points(
+    qdrant: &Qdrant,
+    collection: &str,
+    filter: Filter,
+) -> Result<Vec<RetrievedPoint>, Error> {
+    let exists = qdrant
+        .collection_exists(collection)
+        .await
+        .with_context(|| format!("Failed to check collection of {}", collection))?;
+    if !exists {
+        return Ok(Vec::new());
+    }
+
+    let mut points = Vec::new();
+    let mut offset = None;
+    loop {
+        let mut builder = ScrollPointsBuilder::new(collection)
+            .filter(filter.clone())
+            .with_payload(true)
+            .with_vectors(true)
+            .limit(SCROLL_LIMIT);
+        if let Some(offset) = offset {
+            builder = builder.offset(offset);
+        }
+        let response = qdrant
+            .scroll(builder)
+            .await
+            .with_context(|| format!("Failed to scroll points from {}", collection))?;
+        points.extend(response.result);
+        match response.next_page_offset {
+            Some(next) => offset = Some(next),
+            None => break,
+        }
+    }
+    Ok(points)
+}
+
+const SCROLL_LIMIT: u32 = 256;
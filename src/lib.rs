@@ -1,4 +1,5 @@
 pub mod blunder;
+pub mod command;
 pub mod configuration;
 pub mod database;
 pub mod domain;
@@ -10,3 +11,4 @@ pub mod route;
 pub mod service;
 pub mod startup;
 pub mod telemetry;
+pub mod worker;
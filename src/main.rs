@@ -1,15 +1,24 @@
+use anyhow::Context;
+use clap::Parser;
+use iaudit::command::{self, Cli, Command};
 use iaudit::configuration::setting;
-use iaudit::startup::Application;
 use iaudit::telemetry;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 
 #[tokio::main]
-async fn main() -> std::io::Result<()> {
-    let (subscriber, _guard) = telemetry::get_subscriber("iaudit", "info", std::io::stdout);
-    telemetry::init_subscriber(subscriber);
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Serve);
 
-    let configuration = setting::get_configuration().expect("读取配置文件失败");
+    // 管理命令的结果输出到标准输出, 日志改为输出到标准错误, 并且只记录警告以上的级别
+    let (level, writer) = match command {
+        Command::Serve => ("info", BoxMakeWriter::new(std::io::stdout)),
+        _ => ("warn", BoxMakeWriter::new(std::io::stderr)),
+    };
+    let (subscriber, _guard) = telemetry::get_subscriber("iaudit", level, writer);
+    telemetry::init_subscriber(subscriber);
 
-    let application = Application::build(configuration).await?;
+    let configuration = setting::get_configuration().context("读取配置文件失败")?;
 
-    application.run_until_stopped().await
+    command::execute(command, configuration).await
 }
@@ -1 +1,76 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage};
+use tracing_actix_web::RequestId;
 
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// 读取当前请求的请求编号, 不在请求上下文中调用时返回空字符串
+pub fn request_id() -> String {
+    REQUEST_ID.try_with(Clone::clone).unwrap_or_default()
+}
+
+// 请求编号由TracingLogger生成并写入请求扩展, 这里把它放入任务本地变量,
+// 这样无论是处理器还是`ResponseError::error_response`都可以在构造响应时读取到请求编号
+pub async fn request_context(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), next.call(request))
+        .await?;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::middleware::from_fn;
+    use actix_web::{test, web, App};
+    use serde_json::Value;
+    use tracing_actix_web::TracingLogger;
+
+    use super::*;
+    use crate::blunder::document::DocumentError;
+
+    async fn missing() -> Result<String, DocumentError> {
+        Err(DocumentError::NotFoundError("example".to_string()))
+    }
+
+    #[actix_web::test]
+    async fn error_response_carries_request_id() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(request_context))
+                .wrap(TracingLogger::default())
+                .route("/missing", web::get().to(missing)),
+        )
+        .await;
+
+        let request = test::TestRequest::get().uri("/missing").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status().as_u16(), 404);
+
+        let header = response.headers().get(REQUEST_ID_HEADER).cloned().unwrap();
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body["code"], 404);
+        assert!(body["data"].is_null());
+        assert_eq!(body["request_id"], header.to_str().unwrap());
+    }
+}
@@ -1,8 +1,36 @@
-use actix_web::web::{post, scope};
+use actix_multipart::form::MultipartFormConfig;
+use actix_web::web::{delete, get, post, resource, scope};
 use actix_web::Scope;
 
-use crate::handler::document::thinktank;
+use crate::handler::document::{guideline, thinktank};
 
-pub fn register_document_route() -> Scope {
-    scope("/iaudit/chatgpt/document/thinktank").route("", post().to(thinktank::upload))
+// 批量上传接口使用单独的上传大小限制
+pub fn register_document_route(batch: MultipartFormConfig) -> Scope {
+    scope("/iaudit/chatgpt/document")
+        .service(
+            scope("/thinktank")
+                .route("", post().to(thinktank::upload))
+                .route("", get().to(thinktank::jobs))
+                .service(
+                    resource("/batch")
+                        .app_data(batch.clone())
+                        .route(post().to(thinktank::batch)),
+                )
+                .route("/search", post().to(thinktank::search))
+                .route("/import", post().to(thinktank::import))
+                .route("/{uuid}", get().to(thinktank::status))
+                .route("/{uuid}", delete().to(thinktank::delete)),
+        )
+        .service(
+            scope("/guideline")
+                .route("", post().to(guideline::upload))
+                .route("", get().to(guideline::jobs))
+                .service(
+                    resource("/batch")
+                        .app_data(batch)
+                        .route(post().to(guideline::batch)),
+                )
+                .route("/{uuid}", get().to(guideline::status))
+                .route("/{uuid}", delete().to(guideline::delete)),
+        )
 }
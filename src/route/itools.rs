@@ -0,0 +1,8 @@
+use actix_web::web::{get, scope};
+use actix_web::Scope;
+
+use crate::handler::itools;
+
+pub fn register_itools_route() -> Scope {
+    scope("/iaudit/chatgpt/itools").route("/circuits", get().to(itools::circuits))
+}
@@ -0,0 +1,776 @@
+use std::future::Future;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, ensure, Context, Error};
+use qdrant_client::qdrant::vectors::VectorsOptions;
+use qdrant_client::qdrant::{Condition, Filter, PointId, Range, RetrievedPoint};
+use qdrant_client::{Payload, Qdrant};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::Mutex;
+
+use crate::blunder::document::{DocumentError, ParseError};
+use crate::configuration::itools::{ExtractorKind, ItoolsSettings};
+use crate::configuration::limits::LimitSettings;
+use crate::configuration::splitting::{SplittingSettings, SplittingStrategy};
+use crate::database::ingestion::{self, JobHandle, JobInsertion, JobKind, JobStage};
+use crate::domain::request::document::generally::{
+    DocumentFile, DocumentName, Extension, JobStatus, JobsDomainRequest, UploadDocument,
+};
+use crate::domain::response::document::generally::{
+    BatchJobDomainResponse, BatchUploadDomainResponse, DeleteDomainResponse, JobDomainResponse,
+    JobsDomainResponse,
+};
+use crate::helper::extractor::csv::CsvExtractor;
+use crate::helper::extractor::docx::DocxExtractor;
+use crate::helper::extractor::html::HtmlExtractor;
+use crate::helper::extractor::itools::ItoolsExtractor;
+use crate::helper::extractor::spreadsheet::SpreadsheetExtractor;
+use crate::helper::extractor::text::TextExtractor;
+use crate::helper::extractor::Extractor;
+use crate::helper::splitter::{self, Slice};
+use crate::helper::{cipher, inspector, proxy, vector};
+use crate::worker::ingestion::{IngestionQueue, IngestionTask};
+
+// 检查上传文档的大小、解压后大小、页数和工作表数量, 避免单个异常文档占满磁盘或者拖垮转换服务,
+// doc和ppt的页数在转换之前无法获取, 不做检查; pdf无法统计页数时拒绝上传, 避免绕过页数限制
+pub async fn ensure_limits(
+    file: &DocumentFile,
+    limits: &LimitSettings,
+) -> Result<(), DocumentError> {
+    let extension = file.extension().into_owned();
+    let maximum = limits.file_size(extension.as_str());
+    if file.size() > maximum {
+        return Err(ParseError::FileTooLarge(extension.as_str().to_string(), maximum).into());
+    }
+
+    let filepath = file.path().to_path_buf();
+    let limits = limits.clone();
+    tokio::task::spawn_blocking(move || inspect_limits(&filepath, &extension, &limits))
+        .await
+        .context("Failed to join document inspector")?
+}
+
+fn inspect_limits(
+    filepath: &Path,
+    extension: &Extension,
+    limits: &LimitSettings,
+) -> Result<(), DocumentError> {
+    // 解析失败的细节只记录日志, 响应中不暴露服务端的文件路径
+    let unreadable = |error: anyhow::Error| {
+        tracing::error!(error = ?error, "Failed to inspect document of {:?}", filepath);
+        ParseError::UnreadableFile("无法解析文件结构".to_string())
+    };
+    let open = || std::fs::File::open(filepath).map_err(|error| unreadable(error.into()));
+
+    if matches!(
+        extension,
+        Extension::Docx | Extension::Xlsx | Extension::Pptx
+    ) {
+        let size = inspector::decompressed_size(open()?, limits.max_decompressed_size)
+            .map_err(unreadable)?;
+        if size > limits.max_decompressed_size {
+            return Err(ParseError::DecompressedTooLarge(limits.max_decompressed_size).into());
+        }
+    }
+
+    let limit = limits.max_decompressed_size;
+    let pages = match extension {
+        Extension::Pdf => match inspector::pdf_file_pages(filepath, limit).map_err(unreadable)? {
+            Some(pages) => Some(pages),
+            None => return Err(ParseError::UncountablePages.into()),
+        },
+        Extension::Docx => inspector::ooxml_pages(open()?, "Pages", limit).map_err(unreadable)?,
+        Extension::Pptx => inspector::ooxml_pages(open()?, "Slides", limit).map_err(unreadable)?,
+        _ => None,
+    };
+    if pages.is_some_and(|pages| pages > limits.max_pages) {
+        return Err(ParseError::TooManyPages(limits.max_pages).into());
+    }
+
+    if matches!(extension, Extension::Xls | Extension::Xlsx) {
+        let sheets = inspector::workbook_sheets(filepath).map_err(unreadable)?;
+        if sheets > limits.max_sheets {
+            return Err(ParseError::TooManySheets(limits.max_sheets).into());
+        }
+    }
+    Ok(())
+}
+
+// 将临时文档保存到缓存目录, 返回文档的绝对路径, 转换和提取服务需要使用绝对路径
+pub async fn document_persist(
+    file: &DocumentFile,
+    name: &DocumentName,
+    directory: PathBuf,
+) -> Result<PathBuf, anyhow::Error> {
+    fs::create_dir_all(directory.as_path())
+        .await
+        .with_context(|| format!("Failed to create directory of {:?}", directory))?;
+
+    // 文档名称已经去除路径, 这里再次确认保存位置没有离开文档目录
+    let filepath = directory.join(name.name().as_ref());
+    ensure!(
+        filepath.parent() == Some(directory.as_path()),
+        "Document name {:?} escapes directory of {:?}",
+        name.name(),
+        directory
+    );
+
+    // 待办: 文件保存失败则删除目录
+    file.persist(filepath.as_path())
+        .await
+        .with_context(|| format!("Failed to save document of {:?}", filepath))?;
+
+    let absolute = fs::canonicalize(filepath.as_path())
+        .await
+        .with_context(|| format!("Failed to get absolute of {:?}", filepath))?;
+
+    Ok(absolute)
+}
+
+// 文档在缓存目录中按照`<缓存目录>/<文档主键>/<版本号>/`保存
+pub fn document_directory(cache: &str, uuid: &str, version: i32) -> PathBuf {
+    Path::new(cache).join(uuid).join(version.to_string())
+}
+
+// 保存上传的文档并将任务推进到已保存阶段, 保存失败时记录任务错误, 避免任务一直处于处理中
+pub async fn document_receive(
+    job: JobHandle,
+    file: &DocumentFile,
+    name: &DocumentName,
+    directory: PathBuf,
+    pgpool: &PgPool,
+) -> Result<JobHandle, DocumentError> {
+    if let Err(error) = document_persist(file, name, directory).await {
+        let error = DocumentError::from(error);
+        ingestion::update_error(pgpool, job.id, error.kind(), &format!("{:?}", error)).await?;
+        return Err(error);
+    }
+    ingestion::update_stage(pgpool, job.id, JobStage::Saved).await?;
+    Ok(JobHandle {
+        stage: JobStage::Saved,
+        ..job
+    })
+}
+
+// 已保存文档的绝对路径, 文档没有保存成功的任务无法继续入库
+pub async fn document_filepath(
+    job: JobHandle,
+    directory: PathBuf,
+    name: &DocumentName,
+) -> Result<PathBuf, anyhow::Error> {
+    ensure!(
+        job.stage >= JobStage::Saved,
+        "Document of ingestion job {} was not saved",
+        job.id
+    );
+    let filepath = directory.join(name.name().as_ref());
+    fs::canonicalize(filepath.as_path())
+        .await
+        .with_context(|| format!("Failed to get absolute of {:?}", filepath))
+}
+
+// 依次执行转换、提取、切片和嵌入, 每一步的结果都保存到文档目录下,
+// 服务重启后从最近完成的阶段继续, 不必重新调用已经成功的服务
+#[allow(clippy::too_many_arguments)]
+pub async fn document_processing(
+    job: JobHandle,
+    filepath: PathBuf,
+    extension: &Extension,
+    splitting: &SplittingSettings,
+    limits: &LimitSettings,
+    pgpool: &PgPool,
+    itools: &ItoolsSettings,
+    client: &Client,
+) -> Result<(Vec<Slice>, Vec<Vec<f32>>), anyhow::Error> {
+    let artifacts = filepath.with_file_name(ARTIFACTS_DIRECTORY);
+    fs::create_dir_all(artifacts.as_path())
+        .await
+        .with_context(|| format!("Failed to create directory of {:?}", artifacts))?;
+
+    let slices: Vec<Slice> = if job.stage < JobStage::Split {
+        let extracted: String = if job.stage < JobStage::Extracted {
+            let converted = if job.stage < JobStage::Converted {
+                let converted = document_convertor(client, filepath.clone(), extension, itools)
+                    .await
+                    .with_context(|| {
+                        format!("Failed to run document convertor of {:?}", filepath)
+                    })?;
+                ingestion::update_stage(pgpool, job.id, JobStage::Converted).await?;
+                converted
+            } else {
+                converted_filepath(filepath.clone(), extension)
+            };
+
+            let extracted = document_extractor(client, converted, extension, itools, limits)
+                .await
+                .with_context(|| format!("Failed to run document extractor of {:?}", filepath))?;
+            save_artifact(&artifacts, EXTRACTED_ARTIFACT, &extracted).await?;
+            ingestion::update_stage(pgpool, job.id, JobStage::Extracted).await?;
+            extracted
+        } else {
+            load_artifact(&artifacts, EXTRACTED_ARTIFACT).await?
+        };
+
+        let slices = document_splitting(client, extracted, extension, itools, splitting)
+            .await
+            .with_context(|| format!("Failed to run document splitting of {:?}", filepath))?;
+        save_artifact(&artifacts, SLICES_ARTIFACT, &slices).await?;
+        ingestion::update_stage(pgpool, job.id, JobStage::Split).await?;
+        ingestion::update_slices(pgpool, job.id, slices.len()).await?;
+        slices
+    } else {
+        load_artifact(&artifacts, SLICES_ARTIFACT).await?
+    };
+
+    let vectors: Vec<Vec<f32>> = if job.stage < JobStage::Embedded {
+        let vectors = document_embedding(client, &slices, itools)
+            .await
+            .with_context(|| format!("Failed to run document embedding of {:?}", filepath))?;
+        save_artifact(&artifacts, VECTORS_ARTIFACT, &vectors).await?;
+        ingestion::update_stage(pgpool, job.id, JobStage::Embedded).await?;
+        vectors
+    } else {
+        load_artifact(&artifacts, VECTORS_ARTIFACT).await?
+    };
+
+    Ok((slices, vectors))
+}
+
+const ARTIFACTS_DIRECTORY: &str = ".ingestion";
+const EXTRACTED_ARTIFACT: &str = "extracted.json";
+const SLICES_ARTIFACT: &str = "slices.json";
+const VECTORS_ARTIFACT: &str = "vectors.json";
+
+async fn save_artifact<T: Serialize>(
+    directory: &Path,
+    name: &str,
+    value: &T,
+) -> Result<(), anyhow::Error> {
+    let filepath = directory.join(name);
+    let content = serde_json::to_vec(value)
+        .with_context(|| format!("Failed to serialize artifact of {:?}", filepath))?;
+    fs::write(filepath.as_path(), content)
+        .await
+        .with_context(|| format!("Failed to save artifact of {:?}", filepath))
+}
+
+async fn load_artifact<T: DeserializeOwned>(
+    directory: &Path,
+    name: &str,
+) -> Result<T, anyhow::Error> {
+    let filepath = directory.join(name);
+    let content = fs::read(filepath.as_path())
+        .await
+        .with_context(|| format!("Failed to read artifact of {:?}", filepath))?;
+    serde_json::from_slice(&content)
+        .with_context(|| format!("Failed to deserialize artifact of {:?}", filepath))
+}
+
+pub async fn document_convertor(
+    client: &Client,
+    filepath: PathBuf,
+    extension: &Extension,
+    itools: &ItoolsSettings,
+) -> Result<PathBuf, anyhow::Error> {
+    let proxy = match extension {
+        Extension::Doc => itools.word_to_pdf_proxy(),
+        Extension::Ppt | Extension::Pptx => itools.ppt_to_pdf_proxy(),
+        _ => return Ok(filepath),
+    };
+    proxy::document_convertor(client, &proxy, json!({"filepath": filepath})).await?;
+    Ok(converted_filepath(filepath, extension))
+}
+
+// 转换服务将doc、ppt和pptx文档转换为同名的pdf文档, 其他格式不需要转换
+fn converted_filepath(filepath: PathBuf, extension: &Extension) -> PathBuf {
+    match extension {
+        Extension::Doc | Extension::Ppt | Extension::Pptx => filepath.with_extension("pdf"),
+        _ => filepath,
+    }
+}
+
+// 按照配置选择itools服务或者本地实现提取文本, 本地实现目前支持docx、xls和xlsx,
+// 纯文本、markdown、csv和网页没有对应的itools服务, 始终在本地读取
+pub async fn document_extractor(
+    client: &Client,
+    filepath: PathBuf,
+    extension: &Extension,
+    itools: &ItoolsSettings,
+    limits: &LimitSettings,
+) -> Result<String, anyhow::Error> {
+    match (extension, itools.extractor(extension.as_str())) {
+        (Extension::Txt | Extension::Md, _) => TextExtractor.extract(&filepath).await,
+        (Extension::Csv, _) => CsvExtractor.extract(&filepath).await,
+        (Extension::Html, _) => HtmlExtractor.extract(&filepath).await,
+        (Extension::Docx, ExtractorKind::Native) => {
+            DocxExtractor::new(limits.max_decompressed_size)
+                .extract(&filepath)
+                .await
+        }
+        (Extension::Xls | Extension::Xlsx, ExtractorKind::Native) => {
+            SpreadsheetExtractor.extract(&filepath).await
+        }
+        (_, ExtractorKind::Native) => {
+            bail!("No native extractor for {} document", extension.as_str())
+        }
+        (_, ExtractorKind::Itools) => {
+            let (proxy, options) = match extension {
+                Extension::Xls | Extension::Xlsx => (
+                    itools.xlsx_reader_proxy(),
+                    json!({"readmode": "table", "sheet": ""}),
+                ),
+                Extension::Docx => (itools.docx_reader_proxy(), json!({})),
+                // doc、ppt和pptx已经转换为pdf
+                _ => (itools.pdfx_reader_proxy(), json!({})),
+            };
+            ItoolsExtractor::new(client, proxy, options)
+                .extract(&filepath)
+                .await
+        }
+    }
+}
+
+// 表格文档按行切片, 其他文档按照配置的切片方式切片
+pub async fn document_splitting(
+    client: &Client,
+    extracted: String,
+    extension: &Extension,
+    itools: &ItoolsSettings,
+    splitting: &SplittingSettings,
+) -> Result<Vec<Slice>, anyhow::Error> {
+    if matches!(extension, Extension::Xls | Extension::Xlsx | Extension::Csv) {
+        match splitter::split_sheets(&extracted, splitting.rows_per_slice) {
+            Ok(slices) => return Ok(slices),
+            // 提取结果不是工作表格式时(例如提取服务返回纯文本), 按照普通文本切片
+            Err(error) => tracing::warn!(error = ?error, "Failed to parse extracted sheets"),
+        }
+    }
+    if splitting.strategy != SplittingStrategy::Itools {
+        return Ok(splitter::split(&extracted, splitting)
+            .into_iter()
+            .map(Slice::from)
+            .collect());
+    }
+    let slices = proxy::document_splitting(
+        client,
+        &itools.splitting_proxy(),
+        json!({"content": extracted}),
+    )
+    .await?;
+    // 过滤掉空白切片, 空白切片没有检索价值
+    Ok(slices
+        .into_iter()
+        .filter(|slice| !slice.trim().is_empty())
+        .map(Slice::from)
+        .collect())
+}
+
+pub async fn document_embedding(
+    client: &Client,
+    slices: &[Slice],
+    itools: &ItoolsSettings,
+) -> Result<Vec<Vec<f32>>, anyhow::Error> {
+    let proxy = itools.embedding_proxy();
+    let mut vectors = Vec::with_capacity(slices.len());
+    for slice in slices {
+        let vector =
+            proxy::document_embedding(client, &proxy, json!({"content": slice.text})).await?;
+        vectors.push(vector);
+    }
+    Ok(vectors)
+}
+
+#[tracing::instrument(name = "Query ingestion job status service", skip(pgpool))]
+pub async fn status(
+    pgpool: &PgPool,
+    kind: JobKind,
+    uuid: &str,
+) -> Result<JobDomainResponse, DocumentError> {
+    let job = ingestion::select_latest_job(pgpool, kind, uuid)
+        .await?
+        .ok_or_else(|| DocumentError::NotFoundError(format!("文档{}没有入库任务", uuid)))?;
+    Ok(job.into())
+}
+
+#[tracing::instrument(name = "Query ingestion jobs service", skip(domain, pgpool))]
+pub async fn jobs(
+    domain: JobsDomainRequest,
+    pgpool: &PgPool,
+    kind: JobKind,
+) -> Result<JobsDomainResponse, DocumentError> {
+    let (jobs, total) =
+        ingestion::select_jobs(pgpool, kind, domain.status, domain.offset, domain.size).await?;
+    Ok(JobsDomainResponse {
+        total,
+        page: domain.page,
+        size: domain.size,
+        jobs: jobs.into_iter().map(JobDomainResponse::from).collect(),
+    })
+}
+
+// 依次删除向量、缓存目录并标记记录删除, 某一步失败时继续执行后续步骤, 最终汇总每一步的结果,
+// 重复调用可以重试之前失败的步骤
+#[tracing::instrument(name = "Delete document service", skip(pgpool, qdrant))]
+pub async fn delete(
+    pgpool: &PgPool,
+    qdrant: &Arc<Mutex<Qdrant>>,
+    kind: JobKind,
+    collection: &str,
+    cache: &str,
+    uuid: &str,
+) -> Result<DeleteDomainResponse, DocumentError> {
+    let job = ingestion::select_latest_job(pgpool, kind, uuid)
+        .await?
+        .ok_or_else(|| DocumentError::NotFoundError(format!("文档{}不存在", uuid)))?;
+    if job.status() == JobStatus::Processing && job.deleted_at.is_none() {
+        return Err(DocumentError::ConflictError(format!(
+            "文档{}正在入库, 请稍后再删除",
+            uuid
+        )));
+    }
+
+    let vectors = {
+        let qdrant = qdrant.lock().await;
+        let filter = Filter::must([Condition::matches("uuid", uuid.to_string())]);
+        vector::delete_points(&qdrant, collection, filter).await
+    };
+
+    let directory = Path::new(cache).join(uuid);
+    let files = match fs::remove_dir_all(directory.as_path()).await {
+        Err(error) if error.kind() != ErrorKind::NotFound => {
+            Err(error).with_context(|| format!("Failed to remove directory of {:?}", directory))
+        }
+        _ => Ok(()),
+    };
+
+    let records = ingestion::mark_deleted(pgpool, kind, uuid)
+        .await
+        .map(|_| ());
+
+    // 每一步失败的细节只记录日志, 响应中只返回失败的步骤
+    let step = |result: Result<(), anyhow::Error>, message: &str| {
+        result.map_err(|error| {
+            tracing::error!(error = ?error, "Failed to delete document of {}", uuid);
+            message.to_string()
+        })
+    };
+    let response = DeleteDomainResponse {
+        uuid: uuid.to_string(),
+        vectors: step(vectors, "删除向量失败"),
+        files: step(files, "删除缓存文件失败"),
+        records: step(records, "标记删除记录失败"),
+    };
+    Ok(response)
+}
+
+// 从已保存阶段重新入库文档的最新版本, 用于调整切片或嵌入配置之后, 或者重试失败的任务,
+// 这里不删除已有的向量, 新的切片写入之后再清理该版本残留的旧切片, 重新入库期间文档始终可以检索
+#[tracing::instrument(name = "Reindex document service", skip(pgpool))]
+pub async fn reindex(
+    pgpool: &PgPool,
+    kind: JobKind,
+    uuid: &str,
+) -> Result<JobHandle, DocumentError> {
+    let job = ingestion::select_latest_job(pgpool, kind, uuid)
+        .await?
+        .filter(|job| job.deleted_at.is_none())
+        .ok_or_else(|| DocumentError::NotFoundError(format!("文档{}不存在", uuid)))?;
+    if job.status() == JobStatus::Processing {
+        return Err(DocumentError::ConflictError(format!(
+            "文档{}正在入库, 请稍后再重新入库",
+            uuid
+        )));
+    }
+    let handle = job.handle()?;
+    if handle.stage < JobStage::Saved {
+        return Err(DocumentError::ConflictError(format!(
+            "文档{}没有保存成功, 请重新上传",
+            uuid
+        )));
+    }
+
+    ingestion::reset_job(pgpool, handle.id, JobStage::Saved).await?;
+    Ok(JobHandle {
+        stage: JobStage::Saved,
+        reindex: true,
+        ..handle
+    })
+}
+
+// 批量登记入库任务: 全部文档通过限制检查、没有正在入库的版本并且队列有足够的位置时才登记,
+// 单个文档登记失败不影响其他文档, 失败原因按照错误响应的规则返回
+#[tracing::instrument(
+    name = "Batch receive documents service",
+    skip(documents, pgpool, limits, queue, receive),
+    fields(documents = documents.len())
+)]
+pub async fn batch<T, F, R>(
+    documents: Vec<T>,
+    kind: JobKind,
+    pgpool: &PgPool,
+    limits: &LimitSettings,
+    queue: &IngestionQueue,
+    receive: F,
+) -> Result<BatchUploadDomainResponse, DocumentError>
+where
+    T: UploadDocument,
+    F: Fn(T) -> R,
+    R: Future<Output = Result<JobHandle, DocumentError>>,
+{
+    for (index, document) in documents.iter().enumerate() {
+        ensure_limits(document.file(), limits)
+            .await
+            .map_err(|error| match error {
+                DocumentError::ValidationError(error) => {
+                    ParseError::InvalidBatchItem(index + 1, Box::new(error)).into()
+                }
+                error => error,
+            })?;
+    }
+    for document in &documents {
+        ensure_idle(pgpool, kind, &document.uuid().to_string()).await?;
+    }
+    let permits = queue.reserve_many(documents.len())?;
+
+    let mut jobs = Vec::with_capacity(documents.len());
+    for (document, permit) in documents.into_iter().zip(permits) {
+        let name = document.name().name().into_owned();
+        let uuid = document.uuid().to_string();
+        let job = match receive(document).await {
+            Ok(job) => {
+                permit.send(IngestionTask::new(job.id));
+                BatchJobDomainResponse {
+                    name,
+                    uuid,
+                    job: Some(job.id),
+                    version: Some(job.version),
+                    error: None,
+                }
+            }
+            Err(error) => {
+                tracing::error!(uuid = %uuid, error = ?error, "Failed to receive document");
+                BatchJobDomainResponse {
+                    name,
+                    uuid,
+                    job: None,
+                    version: None,
+                    error: Some(error.message()),
+                }
+            }
+        };
+        jobs.push(job);
+    }
+    Ok(BatchUploadDomainResponse { jobs })
+}
+
+// 同一个文档同时只允许存在一个正在入库的版本, 避免新旧版本的向量相互覆盖,
+// 这里只是在占用队列位置之前提前拒绝, 登记任务时会在事务中再次检查
+pub async fn ensure_idle(pgpool: &PgPool, kind: JobKind, uuid: &str) -> Result<(), DocumentError> {
+    let job = ingestion::select_latest_job(pgpool, kind, uuid).await?;
+    match job {
+        Some(job) if job.status() == JobStatus::Processing && job.deleted_at.is_none() => {
+            Err(busy(uuid, job.version))
+        }
+        _ => Ok(()),
+    }
+}
+
+// 登记入库任务, 并发上传同一个文档时只有一个请求能够登记成功, 其他请求返回冲突
+pub async fn register_job(
+    pgpool: &PgPool,
+    kind: JobKind,
+    uuid: &str,
+    filename: &str,
+    metadata: &Value,
+) -> Result<JobHandle, DocumentError> {
+    match ingestion::insert_job(pgpool, uuid, kind, filename, metadata).await? {
+        JobInsertion::Inserted(job) => Ok(job),
+        JobInsertion::Busy(version) => Err(busy(uuid, version)),
+        JobInsertion::Duplicated => Err(DocumentError::ConflictError(format!(
+            "文档{}正在被其他请求上传, 请稍后再上传",
+            uuid
+        ))),
+    }
+}
+
+fn busy(uuid: &str, version: i32) -> DocumentError {
+    DocumentError::ConflictError(format!(
+        "文档{}的版本{}正在入库, 请稍后再上传",
+        uuid, version
+    ))
+}
+
+// 新版本入库完成后再处理旧版本的向量, 保证替换过程中始终有可检索的版本:
+// 保留旧版本时只将其标记为非最新版本, 仍然可以按版本号检索; 否则直接删除旧版本的向量和文件
+#[tracing::instrument(name = "Retire previous document versions", skip(qdrant, cache))]
+pub async fn retire_versions(
+    qdrant: &Qdrant,
+    collection: &str,
+    cache: &str,
+    uuid: &str,
+    version: i32,
+    retain: bool,
+) -> Result<(), anyhow::Error> {
+    let filter = Filter::must([
+        Condition::matches("uuid", uuid.to_string()),
+        Condition::range(
+            "version",
+            Range {
+                lt: Some(version as f64),
+                ..Default::default()
+            },
+        ),
+    ]);
+
+    if retain {
+        let payload = Payload::try_from(json!({"latest": false}))?;
+        return vector::set_payload(qdrant, collection, payload, filter).await;
+    }
+
+    vector::delete_points(qdrant, collection, filter).await?;
+
+    let directory = Path::new(cache).join(uuid);
+    for previous in 1..version {
+        let directory = directory.join(previous.to_string());
+        if let Err(error) = fs::remove_dir_all(directory.as_path()).await {
+            if error.kind() != ErrorKind::NotFound {
+                tracing::warn!(error = ?error, "Failed to remove directory of {:?}", directory);
+            }
+        }
+    }
+    Ok(())
+}
+
+// 文档内容的哈希值, 用于识别内容相同的文档, 分块读取计算, 与一次性读取全部内容计算的结果相同
+pub async fn document_digest(filepath: &Path) -> Result<String, Error> {
+    let file = fs::File::open(filepath)
+        .await
+        .with_context(|| format!("Failed to open document of {:?}", filepath))?;
+    let length = file.metadata().await?.len();
+    let mut reader = BufReader::with_capacity(DIGEST_BUFFER, file);
+    let mut hasher = cipher::Murmurhash64::new(length);
+    let mut total = 0;
+    loop {
+        let buffer = reader
+            .fill_buf()
+            .await
+            .with_context(|| format!("Failed to read document of {:?}", filepath))?;
+        if buffer.is_empty() {
+            break;
+        }
+        hasher.update(buffer);
+        let read = buffer.len();
+        total += read as u64;
+        reader.consume(read);
+    }
+    ensure!(
+        total == length,
+        "Document of {:?} changed while computing digest",
+        filepath
+    );
+    Ok(hasher.finish().to_string())
+}
+
+const DIGEST_BUFFER: usize = 64 * 1024;
+
+pub fn slice_digest(slice: &str) -> String {
+    cipher::murmurhash64str(slice)
+}
+
+// 点的主键由文档主键、版本、切片序号和切片内容共同决定, 重试入库时会覆盖而不是重复写入
+pub fn point_id(uuid: &str, version: i32, index: usize, digest: &str) -> u64 {
+    cipher::murmurhash64int(format!("{}:{}:{}:{}", uuid, version, index, digest))
+}
+
+// 删除某个版本中不属于本次写入的点, 重新入库后切片数量或内容变化时残留的旧切片在这里清理
+pub async fn prune_points(
+    qdrant: &Qdrant,
+    collection: &str,
+    uuid: &str,
+    version: i32,
+    ids: Vec<PointId>,
+) -> Result<(), Error> {
+    let filter = Filter {
+        must: vec![
+            Condition::matches("uuid", uuid.to_string()),
+            Condition::matches("version", version as i64),
+        ],
+        must_not: vec![Condition::has_id(ids)],
+        ..Default::default()
+    };
+    vector::delete_points(qdrant, collection, filter).await
+}
+
+// 读取已入库文档某个版本的全部切片及其向量, 按切片序号排序
+pub async fn document_slices(
+    qdrant: &Qdrant,
+    collection: &str,
+    uuid: &str,
+    version: i32,
+) -> Result<(Vec<Slice>, Vec<Vec<f32>>), Error> {
+    let filter = Filter::must([
+        Condition::matches("uuid", uuid.to_string()),
+        Condition::matches("version", version as i64),
+    ]);
+    let mut points = vector::scroll_points(qdrant, collection, filter)
+        .await?
+        .into_iter()
+        .map(point_slice)
+        .collect::<Result<Vec<_>, Error>>()?;
+    points.sort_by_key(|(index, _, _)| *index);
+    Ok(points
+        .into_iter()
+        .map(|(_, slice, vector)| (slice, vector))
+        .unzip())
+}
+
+fn point_slice(point: RetrievedPoint) -> Result<(u64, Slice, Vec<f32>), Error> {
+    let payload = Value::from(Payload::from(point.payload));
+    let index = payload["index"]
+        .as_u64()
+        .context("Missing index in payload")?;
+    let text = payload["slice"]
+        .as_str()
+        .context("Missing slice in payload")?
+        .to_string();
+    let slice = Slice {
+        text,
+        sheet: payload["sheet"].as_str().map(str::to_string),
+        headers: serde_json::from_value(payload["headers"].clone()).unwrap_or_default(),
+    };
+    let vector = match point.vectors.and_then(|vectors| vectors.vectors_options) {
+        Some(VectorsOptions::Vector(vector)) => vector.data,
+        _ => anyhow::bail!("Missing vector of slice {}", index),
+    };
+    Ok((index, slice, vector))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point_id_is_deterministic() {
+        let digest = slice_digest("第一条 为了规范审计工作");
+        assert_eq!(
+            point_id("example", 1, 0, &digest),
+            point_id("example", 1, 0, &digest)
+        );
+        assert_ne!(
+            point_id("example", 1, 0, &digest),
+            point_id("example", 2, 0, &digest)
+        );
+        assert_ne!(
+            point_id("example", 1, 0, &digest),
+            point_id("example", 1, 1, &digest)
+        );
+    }
+}
@@ -1 +1,247 @@
+use std::sync::Arc;
 
+use anyhow::Context;
+use qdrant_client::qdrant::PointStruct;
+use qdrant_client::{Payload, Qdrant};
+use reqwest::Client;
+use serde_json::json;
+use sqlx::PgPool;
+use tokio::sync::Mutex;
+
+use crate::blunder::document::DocumentError;
+use crate::configuration::common::CommonSettings;
+use crate::configuration::itools::ItoolsSettings;
+use crate::database::ingestion::{self, JobHandle, JobKind, JobStage};
+use crate::domain::request::document::guideline::{IngestDomainRequest, UploadDomainRequest};
+use crate::helper::splitter::Slice;
+use crate::helper::vector;
+use crate::service::document::generally;
+
+// 登记入库任务并保存上传的文档, 后续处理由入库工作任务根据任务记录完成,
+// 调用方需要事先检查文档的大小和页数等限制
+#[tracing::instrument(
+    name = "Receive audit guideline document service",
+    skip(domain, pgpool, common)
+)]
+pub async fn receive(
+    domain: UploadDomainRequest,
+    pgpool: &PgPool,
+    common: &CommonSettings,
+) -> Result<JobHandle, DocumentError> {
+    let (file, domain) = domain.into_parts();
+    let uuid = domain.uuid.to_string();
+    let metadata =
+        serde_json::to_value(&domain).context("Failed to serialize metadata of document")?;
+    let job = generally::register_job(
+        pgpool,
+        JobKind::Guideline,
+        &uuid,
+        &domain.name.name(),
+        &metadata,
+    )
+    .await?;
+
+    let directory = generally::document_directory(&common.guideline_cache, &uuid, job.version);
+    generally::document_receive(job, &file, &domain.name, directory, pgpool).await
+}
+
+// 从任务最近完成的阶段继续入库, 新登记的任务从已保存阶段开始
+#[tracing::instrument(
+    name = "Ingest audit guideline document service",
+    skip(domain, pgpool, qdrant, itools, common, client)
+)]
+pub async fn ingest(
+    job: JobHandle,
+    domain: IngestDomainRequest,
+    pgpool: &PgPool,
+    qdrant: &Arc<Mutex<Qdrant>>,
+    itools: &ItoolsSettings,
+    common: &CommonSettings,
+    client: &Client,
+) -> Result<(), DocumentError> {
+    let uuid = domain.uuid.to_string();
+    let directory = generally::document_directory(&common.guideline_cache, &uuid, job.version);
+    let filepath = generally::document_filepath(job, directory, &domain.name).await?;
+    let extension = domain.name.extension();
+
+    if job.stage < JobStage::Converted {
+        let digest = generally::document_digest(&filepath).await?;
+        ingestion::update_digest(pgpool, job.id, &digest).await?;
+        // 重新入库是为了应用新的切片和嵌入配置, 不能复用已有的切片和向量
+        if !job.reindex && document_linking(job, &domain, &digest, pgpool, qdrant, common).await? {
+            return Ok(());
+        }
+    }
+
+    let (slices, vectors) = generally::document_processing(
+        job,
+        filepath.clone(),
+        extension.as_ref(),
+        &common.guideline_splitting,
+        &common.limits,
+        pgpool,
+        itools,
+        client,
+    )
+    .await?;
+
+    document_indexing(qdrant, &domain, job.version, slices, vectors, common)
+        .await
+        .with_context(|| format!("Failed to run document indexing of {:?}", filepath))?;
+    ingestion::update_stage(pgpool, job.id, JobStage::Indexed).await?;
+
+    Ok(())
+}
+
+// 内容相同的文档已经入库时, 直接复用已有的切片和向量, 跳过转换、提取、切片和嵌入,
+// 已有的向量不存在时(例如旧版本已被删除)返回false, 由调用方继续完整的入库流程
+async fn document_linking(
+    job: JobHandle,
+    domain: &IngestDomainRequest,
+    digest: &str,
+    pgpool: &PgPool,
+    qdrant: &Arc<Mutex<Qdrant>>,
+    common: &CommonSettings,
+) -> Result<bool, anyhow::Error> {
+    let Some(source) =
+        ingestion::select_duplicate(pgpool, JobKind::Guideline, digest, job.id).await?
+    else {
+        return Ok(false);
+    };
+
+    let (slices, vectors) = {
+        let qdrant = qdrant.lock().await;
+        generally::document_slices(
+            &qdrant,
+            &common.guideline_collection,
+            &source.uuid,
+            source.version,
+        )
+        .await?
+    };
+    if slices.is_empty() {
+        return Ok(false);
+    }
+
+    tracing::info!(source = %source.id, "Reuse slices and vectors of identical document");
+    ingestion::update_slices(pgpool, job.id, slices.len()).await?;
+    document_indexing(qdrant, domain, job.version, slices, vectors, common).await?;
+    ingestion::update_duplicate(pgpool, job.id, source.id).await?;
+    ingestion::update_stage(pgpool, job.id, JobStage::Indexed).await?;
+    Ok(true)
+}
+
+pub async fn document_indexing(
+    qdrant: &Arc<Mutex<Qdrant>>,
+    domain: &IngestDomainRequest,
+    version: i32,
+    slices: Vec<Slice>,
+    vectors: Vec<Vec<f32>>,
+    common: &CommonSettings,
+) -> Result<(), anyhow::Error> {
+    let Some(size) = vectors.first().map(|vector| vector.len() as u64) else {
+        tracing::warn!("No slices extracted from document, skip indexing");
+        return Ok(());
+    };
+
+    let clauses = clause_numbering(&slices);
+
+    let uuid = domain.uuid.to_string();
+    let points = slices
+        .into_iter()
+        .zip(vectors)
+        .zip(clauses)
+        .enumerate()
+        .map(|(index, ((slice, vector), clause))| {
+            let digest = generally::slice_digest(&slice.text);
+            let payload = Payload::try_from(json!({
+                "uuid": domain.uuid,
+                "name": domain.name.name(),
+                "date": domain.date.to_string(),
+                "head": domain.head.as_str(),
+                "auth": domain.auth,
+                "exec": domain.exec.to_string(),
+                "clause": clause,
+                "version": version,
+                "latest": true,
+                "index": index,
+                "slice": slice.text,
+                "sheet": slice.sheet,
+                "headers": slice.headers,
+                "digest": digest,
+            }))?;
+            let id = generally::point_id(&uuid, version, index, &digest);
+            Ok(PointStruct::new(id, vector, payload))
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    let qdrant = qdrant.lock().await;
+    vector::ensure_collection(&qdrant, &common.guideline_collection, size).await?;
+    let ids = points.iter().filter_map(|point| point.id.clone()).collect();
+    vector::upsert_points(&qdrant, &common.guideline_collection, points).await?;
+    generally::prune_points(&qdrant, &common.guideline_collection, &uuid, version, ids).await?;
+    generally::retire_versions(
+        &qdrant,
+        &common.guideline_collection,
+        &common.guideline_cache,
+        &uuid,
+        version,
+        common.retain_versions,
+    )
+    .await
+}
+
+// 为每个切片标注所属条款, 切片开头没有条款编号时沿用上一个切片的条款编号
+fn clause_numbering(slices: &[Slice]) -> Vec<Option<String>> {
+    slices
+        .iter()
+        .scan(None, |current: &mut Option<String>, slice| {
+            if let Some(clause) = clause_number(&slice.text) {
+                *current = Some(clause);
+            }
+            Some(current.clone())
+        })
+        .collect()
+}
+
+// 识别切片开头的条款编号, 例如: 第十二条、第3条
+fn clause_number(slice: &str) -> Option<String> {
+    let rest = slice.trim_start().strip_prefix('第')?;
+    let (number, _) = rest.split_once('条')?;
+    let valid = !number.is_empty()
+        && number.chars().count() <= 8
+        && number
+            .chars()
+            .all(|c| c.is_ascii_digit() || "零〇一二两三四五六七八九十百千".contains(c));
+    valid.then(|| format!("第{}条", number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_clause_number() {
+        assert_eq!(clause_number("第十二条 审计机关"), Some("第十二条".into()));
+        assert_eq!(clause_number("  第3条"), Some("第3条".into()));
+        assert_eq!(clause_number("第一章 总则"), None);
+        assert_eq!(clause_number("本条例所称"), None);
+    }
+
+    #[test]
+    fn inherit_clause_number() {
+        let slices: Vec<Slice> = ["总则", "第一条 为了规范", "续写内容", "第二条 适用范围"]
+            .into_iter()
+            .map(|text| Slice::from(text.to_string()))
+            .collect();
+        assert_eq!(
+            clause_numbering(&slices),
+            vec![
+                None,
+                Some("第一条".into()),
+                Some("第一条".into()),
+                Some("第二条".into())
+            ]
+        );
+    }
+}
@@ -1,104 +1,613 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::Context;
-use qdrant_client::Qdrant;
+use actix_multipart::form::tempfile::TempFile;
+use anyhow::{ensure, Context};
+use chrono::NaiveDate;
+use qdrant_client::qdrant::{
+    Condition, DatetimeRange, Filter, PointStruct, ScoredPoint, Timestamp,
+};
+use qdrant_client::{Payload, Qdrant};
 use reqwest::Client;
-use serde_json::json;
-use tokio::fs;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tempfile::NamedTempFile;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::Permit;
 use tokio::sync::Mutex;
 
-use crate::blunder::document::DocumentError;
+use crate::blunder::document::{DocumentError, ParseError};
 use crate::configuration::common::CommonSettings;
 use crate::configuration::itools::ItoolsSettings;
-use crate::domain::request::document::generally::Extension;
-use crate::domain::request::document::thinktank::UploadDomainRequest;
-use crate::helper::proxy;
+use crate::database::ingestion::{self, JobHandle, JobKind, JobStage};
+use crate::domain::request::document::thinktank::{
+    ImportDomainRequest, IngestDomainRequest, SearchDomainRequest, UploadDomainRequest,
+};
+use crate::domain::response::document::thinktank::{
+    ImportDomainResponse, ImportProgress, ImportStatus, SearchDomainResponse, SliceDomainResponse,
+};
+use crate::dto::request::document::thinktank::ImportEntry;
+use crate::helper::splitter::Slice;
+use crate::helper::{cipher, proxy, vector};
+use crate::service::document::generally;
+use crate::worker::ingestion::{IngestionQueue, IngestionTask};
 
+// 登记入库任务并保存上传的文档, 后续处理由入库工作任务根据任务记录完成,
+// 调用方需要事先检查文档的大小和页数等限制
 #[tracing::instrument(
-    name = "Upload audit thinktank document service",
-    skip(domain, qdrant, itools, common)
+    name = "Receive audit thinktank document service",
+    skip(domain, pgpool, common)
 )]
-pub async fn upload(
+pub async fn receive(
     domain: UploadDomainRequest,
-    qdrant: &Arc<Mutex<Qdrant>>,
-    itools: &ItoolsSettings,
+    pgpool: &PgPool,
     common: &CommonSettings,
-    client: &Client,
-) -> Result<(), DocumentError> {
-    println!("{}", Arc::strong_count(qdrant));
+) -> Result<JobHandle, DocumentError> {
+    let (file, domain) = domain.into_parts();
+    let uuid = domain.uuid.to_string();
+    let metadata =
+        serde_json::to_value(&domain).context("Failed to serialize metadata of document")?;
+    let job = generally::register_job(
+        pgpool,
+        JobKind::Thinktank,
+        &uuid,
+        &domain.name.name(),
+        &metadata,
+    )
+    .await?;
+
+    let directory = generally::document_directory(&common.thinktank_cache, &uuid, job.version);
+    generally::document_receive(job, &file, &domain.name, directory, pgpool).await
+}
+
+// 导入进度保存在缓存目录中, 以文档清单路径的哈希值命名, 每处理一个文档追加一行
+const IMPORTS_DIRECTORY: &str = ".imports";
 
-    let directory = Path::new(common.thinktank_cache.as_str()).join(domain.uuid);
+// 按文档清单的顺序逐个导入, 已经登记的文档直接跳过, 失败的文档在下次导入时重试,
+// 入库队列已满时停止导入, 剩余的文档等待下次导入; 只校验时不登记任务也不记录进度
+#[tracing::instrument(
+    name = "Import audit thinktank documents service",
+    skip(domain, pgpool, common, queue),
+    fields(manifest = %domain.manifest, dry_run = domain.dry_run)
+)]
+pub async fn import(
+    domain: ImportDomainRequest,
+    pgpool: &PgPool,
+    common: &CommonSettings,
+    queue: &IngestionQueue,
+) -> Result<ImportDomainResponse, DocumentError> {
+    let (directory, manifest) = import_paths(&domain, common).await?;
+    let entries = import_manifest(&manifest).await?;
+
+    // 同一清单的导入进度保存在一起
+    let progress_directory = Path::new(&common.thinktank_cache).join(IMPORTS_DIRECTORY);
+    let manifest = manifest.to_string_lossy();
+    let filepath =
+        progress_directory.join(format!("{}.jsonl", cipher::murmurhash64str(&*manifest)));
+    let progress = import_progress(&filepath).await?;
+    let mut log = match domain.dry_run {
+        true => None,
+        false => {
+            fs::create_dir_all(&progress_directory)
+                .await
+                .with_context(|| {
+                    format!("Failed to create directory of {:?}", progress_directory)
+                })?;
+            let log = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&filepath)
+                .await
+                .with_context(|| format!("Failed to open import progress of {:?}", filepath))?;
+            Some(log)
+        }
+    };
+
+    let mut records = Vec::with_capacity(entries.len());
+    let mut exhausted = false;
+    for entry in entries {
+        let (file, uuid) = (entry.file.clone(), entry.uuid.clone());
+        if let Some(record) = progress
+            .get(&file)
+            .filter(|record| record.status == ImportStatus::Queued)
+        {
+            records.push(record.clone());
+            continue;
+        }
 
-    fs::create_dir_all(directory.as_path())
+        // 先占用队列位置再登记入库任务, 与上传接口保持一致
+        let permit = match (domain.dry_run, exhausted) {
+            (true, _) => None,
+            (false, true) => {
+                records.push(ImportProgress::pending(file, uuid));
+                continue;
+            }
+            (false, false) => match queue.reserve() {
+                Ok(permit) => Some(permit),
+                Err(error) => {
+                    tracing::warn!(error = %error, "Stop importing documents");
+                    exhausted = true;
+                    records.push(ImportProgress::pending(file, uuid));
+                    continue;
+                }
+            },
+        };
+
+        let record = match import_document(entry, &directory, permit, pgpool, common).await {
+            Ok(Some(job)) => ImportProgress::queued(file, uuid, job.id, job.version),
+            Ok(None) => ImportProgress::valid(file, uuid),
+            Err(error) => {
+                tracing::error!(file = %file, error = ?error, "Failed to import document");
+                ImportProgress::failed(file, uuid, error.message())
+            }
+        };
+        if let Some(log) = &mut log {
+            append_progress(log, &record).await?;
+        }
+        records.push(record);
+    }
+
+    Ok(ImportDomainResponse {
+        dry_run: domain.dry_run,
+        entries: records,
+    })
+}
+
+// 导入目录必须位于配置允许的目录中, 文档清单必须位于导入目录中, 符号链接按实际路径判断
+async fn import_paths(
+    domain: &ImportDomainRequest,
+    common: &CommonSettings,
+) -> Result<(PathBuf, PathBuf), DocumentError> {
+    let directory = match fs::canonicalize(&domain.directory).await {
+        Ok(directory)
+            if fs::metadata(&directory)
+                .await
+                .is_ok_and(|meta| meta.is_dir()) =>
+        {
+            directory
+        }
+        _ => return Err(ParseError::InvalidDirectory(domain.directory.clone()).into()),
+    };
+    let mut permitted = false;
+    for root in &common.import_roots {
+        if let Ok(root) = fs::canonicalize(root).await {
+            permitted |= directory.starts_with(root);
+        }
+    }
+    if !permitted {
+        return Err(ParseError::ForbiddenDirectory(domain.directory.clone()).into());
+    }
+
+    let manifest = match fs::canonicalize(directory.join(&domain.manifest)).await {
+        Ok(manifest)
+            if fs::metadata(&manifest)
+                .await
+                .is_ok_and(|meta| meta.is_file()) =>
+        {
+            manifest
+        }
+        _ => return Err(ParseError::MissingManifest(domain.manifest.clone()).into()),
+    };
+    if !manifest.starts_with(&directory) {
+        return Err(ParseError::ForbiddenFile(domain.manifest.clone()).into());
+    }
+    Ok((directory, manifest))
+}
+
+async fn import_manifest(manifest: &Path) -> Result<Vec<ImportEntry>, DocumentError> {
+    let format = manifest
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let content = fs::read(manifest)
         .await
-        .with_context(|| format!("Failed to create directory of {:?}", directory))?;
+        .map_err(|error| ParseError::UnreadableFile(error.to_string()))?;
+    let entries = import_entries(&content, &format)?;
+    if entries.is_empty() {
+        return Err(ParseError::EmptyBatch.into());
+    }
+    Ok(entries)
+}
 
-    let filename = domain.name.name();
-    let filepath = directory.join(filename.as_ref());
-    let extension = domain.name.extension();
+// csv清单的第一行为列名, 列名与json清单的字段名相同, 文档名称列可以省略或留空
+fn import_entries(content: &[u8], format: &str) -> Result<Vec<ImportEntry>, ParseError> {
+    let invalid = |error: String| ParseError::InvalidManifest(error);
+    match format {
+        "csv" => csv::Reader::from_reader(content)
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .map_err(|error| invalid(error.to_string())),
+        "json" => serde_json::from_slice(content).map_err(|error| invalid(error.to_string())),
+        _ => Err(invalid("只支持csv和json格式".to_string())),
+    }
+}
+
+// 没有队列位置时表示只校验, 校验内容与登记入库任务时相同
+async fn import_document(
+    entry: ImportEntry,
+    directory: &Path,
+    permit: Option<Permit<'_, IngestionTask>>,
+    pgpool: &PgPool,
+    common: &CommonSettings,
+) -> Result<Option<JobHandle>, DocumentError> {
+    let file = import_file(directory, &entry.file).await?;
+    let domain: UploadDomainRequest = (file, entry, &common.vocabulary)
+        .try_into()
+        .map_err(DocumentError::ValidationError)?;
+    generally::ensure_limits(&domain.file, &common.limits).await?;
+    generally::ensure_idle(pgpool, JobKind::Thinktank, &domain.uuid.to_string()).await?;
+
+    let Some(permit) = permit else {
+        return Ok(None);
+    };
+    let job = receive(domain, pgpool, common).await?;
+    permit.send(IngestionTask::new(job.id));
+    Ok(Some(job))
+}
 
-    // 待办: 文件保存失败则删除目录
-    domain
-        .file
-        .persist(filepath.as_path())
+// 将导入目录中的文档复制为临时文档, 原始文档保持不变, 解析符号链接后的路径必须仍在导入目录中
+async fn import_file(directory: &Path, file: &str) -> Result<TempFile, DocumentError> {
+    let unreadable =
+        |error: std::io::Error| ParseError::UnreadableFile(format!("{}: {}", file, error));
+    let filepath = fs::canonicalize(directory.join(file))
         .await
-        .with_context(|| format!("Failed to save document of {:?}", filepath))?;
+        .map_err(unreadable)?;
+    if !filepath.starts_with(directory) {
+        return Err(ParseError::ForbiddenFile(file.to_string()).into());
+    }
 
-    let absolute = tokio::fs::canonicalize(filepath.as_path())
+    let temporary = NamedTempFile::new().context("Failed to create temporary file")?;
+    let size = fs::copy(&filepath, temporary.path())
         .await
-        .with_context(|| format!("Failed to get absolute of {:?}", filepath))?;
+        .map_err(unreadable)?;
+    Ok(TempFile {
+        file: temporary,
+        content_type: None,
+        file_name: Path::new(file)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned()),
+        size: size as usize,
+    })
+}
+
+// 同一个文档可能有多条进度记录, 以最后一条为准
+async fn import_progress(
+    filepath: &Path,
+) -> Result<HashMap<String, ImportProgress>, anyhow::Error> {
+    let content = match fs::read_to_string(filepath).await {
+        Ok(content) => content,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(error) => {
+            return Err(error)
+                .with_context(|| format!("Failed to read import progress of {:?}", filepath))
+        }
+    };
+    Ok(merge_progress(&content))
+}
+
+// 进程中断时最后一行可能不完整, 无法解析的行直接忽略
+fn merge_progress(content: &str) -> HashMap<String, ImportProgress> {
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<ImportProgress>(line).ok())
+        .map(|record| (record.file.clone(), record))
+        .collect()
+}
 
-    let converted = document_convertor(client, absolute, extension.as_ref(), itools)
+async fn append_progress(log: &mut File, record: &ImportProgress) -> Result<(), anyhow::Error> {
+    let mut line = serde_json::to_vec(record).context("Failed to serialize import progress")?;
+    line.push(b'\n');
+    log.write_all(&line)
         .await
-        .with_context(|| format!("Failed to run document convertor of {:?}", filepath))?;
+        .context("Failed to append import progress")?;
+    log.flush().await.context("Failed to flush import progress")
+}
+
+// 从任务最近完成的阶段继续入库, 新登记的任务从已保存阶段开始
+#[tracing::instrument(
+    name = "Ingest audit thinktank document service",
+    skip(domain, pgpool, qdrant, itools, common, client)
+)]
+pub async fn ingest(
+    job: JobHandle,
+    domain: IngestDomainRequest,
+    pgpool: &PgPool,
+    qdrant: &Arc<Mutex<Qdrant>>,
+    itools: &ItoolsSettings,
+    common: &CommonSettings,
+    client: &Client,
+) -> Result<(), DocumentError> {
+    let uuid = domain.uuid.to_string();
+    let directory = generally::document_directory(&common.thinktank_cache, &uuid, job.version);
+    let filepath = generally::document_filepath(job, directory, &domain.name).await?;
+    let extension = domain.name.extension();
 
-    let extracted = document_extractor(client, converted, extension.as_ref(), itools)
+    if job.stage < JobStage::Converted {
+        let digest = generally::document_digest(&filepath).await?;
+        ingestion::update_digest(pgpool, job.id, &digest).await?;
+        // 重新入库是为了应用新的切片和嵌入配置, 不能复用已有的切片和向量
+        if !job.reindex && document_linking(job, &domain, &digest, pgpool, qdrant, common).await? {
+            return Ok(());
+        }
+    }
+
+    let (slices, vectors) = generally::document_processing(
+        job,
+        filepath.clone(),
+        extension.as_ref(),
+        &common.thinktank_splitting,
+        &common.limits,
+        pgpool,
+        itools,
+        client,
+    )
+    .await?;
+
+    document_indexing(qdrant, &domain, job.version, slices, vectors, common)
         .await
-        .with_context(|| format!("Failed to run document extractor of {:?}", filepath))?;
-    println!("{}", extracted);
+        .with_context(|| format!("Failed to run document indexing of {:?}", filepath))?;
+    ingestion::update_stage(pgpool, job.id, JobStage::Indexed).await?;
 
     Ok(())
 }
 
-pub async fn document_convertor(
-    client: &Client,
-    filepath: PathBuf,
-    extension: &Extension,
-    itools: &ItoolsSettings,
-) -> Result<PathBuf, anyhow::Error> {
-    if !matches!(extension, Extension::Doc) {
-        return Ok(filepath);
+// 内容相同的文档已经入库时, 直接复用已有的切片和向量, 跳过转换、提取、切片和嵌入,
+// 已有的向量不存在时(例如旧版本已被删除)返回false, 由调用方继续完整的入库流程
+async fn document_linking(
+    job: JobHandle,
+    domain: &IngestDomainRequest,
+    digest: &str,
+    pgpool: &PgPool,
+    qdrant: &Arc<Mutex<Qdrant>>,
+    common: &CommonSettings,
+) -> Result<bool, anyhow::Error> {
+    let Some(source) =
+        ingestion::select_duplicate(pgpool, JobKind::Thinktank, digest, job.id).await?
+    else {
+        return Ok(false);
+    };
+
+    let (slices, vectors) = {
+        let qdrant = qdrant.lock().await;
+        generally::document_slices(
+            &qdrant,
+            &common.thinktank_collection,
+            &source.uuid,
+            source.version,
+        )
+        .await?
+    };
+    if slices.is_empty() {
+        return Ok(false);
     }
-    proxy::document_convertor(
+
+    tracing::info!(source = %source.id, "Reuse slices and vectors of identical document");
+    ingestion::update_slices(pgpool, job.id, slices.len()).await?;
+    document_indexing(qdrant, domain, job.version, slices, vectors, common).await?;
+    ingestion::update_duplicate(pgpool, job.id, source.id).await?;
+    ingestion::update_stage(pgpool, job.id, JobStage::Indexed).await?;
+    Ok(true)
+}
+
+pub async fn document_indexing(
+    qdrant: &Arc<Mutex<Qdrant>>,
+    domain: &IngestDomainRequest,
+    version: i32,
+    slices: Vec<Slice>,
+    vectors: Vec<Vec<f32>>,
+    common: &CommonSettings,
+) -> Result<(), anyhow::Error> {
+    let Some(size) = vectors.first().map(|vector| vector.len() as u64) else {
+        tracing::warn!("No slices extracted from document, skip indexing");
+        return Ok(());
+    };
+
+    let uuid = domain.uuid.to_string();
+    let points = slices
+        .into_iter()
+        .zip(vectors)
+        .enumerate()
+        .map(|(index, (slice, vector))| {
+            let digest = generally::slice_digest(&slice.text);
+            let payload = Payload::try_from(json!({
+                "uuid": domain.uuid,
+                "name": domain.name.name(),
+                "date": domain.date.to_string(),
+                "head": domain.head.as_str(),
+                "hold": domain.hold,
+                "area": domain.area.as_str(),
+                "stem": domain.stem.as_str(),
+                "version": version,
+                "latest": true,
+                "index": index,
+                "slice": slice.text,
+                "sheet": slice.sheet,
+                "headers": slice.headers,
+                "digest": digest,
+            }))?;
+            let id = generally::point_id(&uuid, version, index, &digest);
+            Ok(PointStruct::new(id, vector, payload))
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    let qdrant = qdrant.lock().await;
+    vector::ensure_collection(&qdrant, &common.thinktank_collection, size).await?;
+    let ids = points.iter().filter_map(|point| point.id.clone()).collect();
+    vector::upsert_points(&qdrant, &common.thinktank_collection, points).await?;
+    generally::prune_points(&qdrant, &common.thinktank_collection, &uuid, version, ids).await?;
+    generally::retire_versions(
+        &qdrant,
+        &common.thinktank_collection,
+        &common.thinktank_cache,
+        &uuid,
+        version,
+        common.retain_versions,
+    )
+    .await
+}
+
+// 向量检索的候选数量是返回数量的倍数, 候选结果再经过重排模型排序后截取
+const RERANKING_FACTOR: u64 = 4;
+
+#[tracing::instrument(
+    name = "Search audit thinktank document service",
+    skip(domain, qdrant, itools, common, client),
+    fields(question = %domain.text)
+)]
+pub async fn search(
+    domain: SearchDomainRequest,
+    qdrant: &Arc<Mutex<Qdrant>>,
+    itools: &ItoolsSettings,
+    common: &CommonSettings,
+    client: &Client,
+) -> Result<SearchDomainResponse, DocumentError> {
+    let vector = proxy::document_embedding(
         client,
-        &itools.word_to_pdf_proxy(),
-        json!({"filepath": filepath}),
+        &itools.embedding_proxy(),
+        json!({"content": domain.text}),
     )
     .await?;
-    Ok(filepath.with_extension("pdf"))
+
+    let candidates = {
+        let qdrant = qdrant.lock().await;
+        vector::query_points(
+            &qdrant,
+            &common.thinktank_collection,
+            vector,
+            search_filter(&domain),
+            domain.topk * RERANKING_FACTOR,
+        )
+        .await?
+    };
+
+    let mut slices = candidates
+        .into_iter()
+        .map(search_slice)
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    document_reranking(client, &domain.text, &mut slices, itools)
+        .await
+        .context("Failed to run slice reranking")?;
+    slices.truncate(domain.topk as usize);
+
+    Ok(SearchDomainResponse { slices })
 }
 
-pub async fn document_extractor(
+pub async fn document_reranking(
     client: &Client,
-    filepath: PathBuf,
-    extension: &Extension,
+    question: &str,
+    slices: &mut [SliceDomainResponse],
     itools: &ItoolsSettings,
-) -> Result<String, anyhow::Error> {
-    let (proxy, value) = match extension {
-        Extension::Xls | Extension::Xlsx => (
-            itools.xlsx_reader_proxy(),
-            json!({"filepath": filepath, "readmode": "table", "sheet": ""}),
-        ),
-        Extension::Doc | Extension::Pdf => {
-            (itools.pdfx_reader_proxy(), json!({"filepath": filepath}))
-        }
-        Extension::Docx => (itools.docx_reader_proxy(), json!({"filepath": filepath})),
+) -> Result<(), anyhow::Error> {
+    if slices.is_empty() {
+        return Ok(());
+    }
+    let contents: Vec<&str> = slices.iter().map(|slice| slice.slice.as_str()).collect();
+    let scores = proxy::document_reranking(
+        client,
+        &itools.reranking_proxy(),
+        json!({"query": question, "contents": contents}),
+    )
+    .await?;
+    ensure!(
+        scores.len() == slices.len(),
+        "Reranking returned {} scores for {} slices",
+        scores.len(),
+        slices.len()
+    );
+    slices
+        .iter_mut()
+        .zip(scores)
+        .for_each(|(slice, score)| slice.score = score);
+    slices.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(())
+}
+
+fn search_filter(domain: &SearchDomainRequest) -> Filter {
+    let mut conditions = Vec::new();
+    if let Some(hold) = &domain.hold {
+        conditions.push(Condition::matches("hold", hold.clone()));
+    }
+    if let Some(area) = &domain.area {
+        conditions.push(Condition::matches("area", area.clone()));
+    }
+    if let Some(stem) = &domain.stem {
+        conditions.push(Condition::matches("stem", stem.clone()));
+    }
+    if let Some(vers) = domain.vers {
+        conditions.push(Condition::matches("version", vers as i64));
+    }
+    if domain.from.is_some() || domain.till.is_some() {
+        conditions.push(Condition::datetime_range(
+            "date",
+            DatetimeRange {
+                gte: domain.from.map(timestamp),
+                lte: domain.till.map(timestamp),
+                ..Default::default()
+            },
+        ));
+    }
+    // 未指定版本时排除已被新版本替代的切片, 早期入库的切片没有latest字段, 同样视为最新版本
+    let exclusions = match domain.vers {
+        Some(_) => Vec::new(),
+        None => vec![Condition::matches("latest", false)],
     };
-    proxy::document_extractor(client, &proxy, value).await
+    Filter {
+        must: conditions,
+        must_not: exclusions,
+        ..Default::default()
+    }
+}
+
+fn timestamp(date: NaiveDate) -> Timestamp {
+    Timestamp {
+        seconds: date.and_time(Default::default()).and_utc().timestamp(),
+        nanos: 0,
+    }
+}
+
+#[derive(Deserialize)]
+struct SlicePayload {
+    uuid: String,
+    name: String,
+    date: String,
+    head: String,
+    hold: String,
+    area: String,
+    stem: String,
+    #[serde(default)]
+    version: Option<i32>,
+    index: u64,
+    slice: String,
+    #[serde(default)]
+    sheet: Option<String>,
+    #[serde(default)]
+    headers: Vec<String>,
 }
 
-pub async fn document_splitting() {}
+fn search_slice(point: ScoredPoint) -> Result<SliceDomainResponse, anyhow::Error> {
+    let payload: SlicePayload = serde_json::from_value(Value::from(Payload::from(point.payload)))
+        .context("Failed to deserialize slice payload")?;
+    Ok(SliceDomainResponse {
+        uuid: payload.uuid,
+        name: payload.name,
+        date: payload.date,
+        head: payload.head,
+        hold: payload.hold,
+        area: payload.area,
+        stem: payload.stem,
+        vers: payload.version.unwrap_or(1),
+        index: payload.index,
+        slice: payload.slice,
+        sheet: payload.sheet,
+        headers: payload.headers,
+        score: point.score,
+        similarity: point.score,
+    })
+}
 
 // 文档转换
 // 读文件内容
@@ -125,3 +634,50 @@ pub async fn document_splitting() {}
 
 //     Ok(result)
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_import_progress() {
+        let failed = ImportProgress::failed("a.pdf".into(), "1".into(), "文件读取失败".into());
+        let queued = ImportProgress::queued("a.pdf".into(), "1".into(), uuid::Uuid::nil(), 2);
+        let valid = ImportProgress::valid("b.pdf".into(), "2".into());
+        let content = [&failed, &queued, &valid]
+            .iter()
+            .map(|record| serde_json::to_string(record).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n{\"file\": \"c.pdf\", \"uu";
+
+        let progress = merge_progress(&content);
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress["a.pdf"].status, ImportStatus::Queued);
+        assert_eq!(progress["a.pdf"].version, Some(2));
+        assert_eq!(progress["b.pdf"].status, ImportStatus::Valid);
+    }
+
+    #[test]
+    fn parse_import_manifest() {
+        let content = "\u{FEFF}file,name,uuid,date,title,owner,range,source\n\
+            2024/报告.pdf,,8d6e5b7a-1c2f-4e3a-9b4d-5f6a7b8c9d0e,2024-05-01,审计报告,审计一处,内部审计,审计署\n";
+        let entries = import_entries(content.as_bytes(), "csv").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].file, "2024/报告.pdf");
+        assert!(entries[0].name.is_none());
+        assert_eq!(entries[0].title, "审计报告");
+
+        let content = r#"[{"file": "a.txt", "uuid": "u", "date": "d", "title": "t",
+            "owner": "o", "range": "r", "source": "s"}]"#;
+        assert_eq!(import_entries(content.as_bytes(), "json").unwrap().len(), 1);
+        assert!(matches!(
+            import_entries(b"file\na.txt\n", "csv"),
+            Err(ParseError::InvalidManifest(_))
+        ));
+        assert!(matches!(
+            import_entries(b"", "xlsx"),
+            Err(ParseError::InvalidManifest(_))
+        ));
+    }
+}
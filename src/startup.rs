@@ -1,27 +1,47 @@
 use std::net::TcpListener;
 use std::sync::Arc;
 
+use actix_multipart::form::MultipartFormConfig;
 use actix_web::dev::Server;
-use actix_web::web::{Data, JsonConfig};
-use actix_web::{error, App, HttpResponse, HttpServer};
-use qdrant_client::Qdrant;
-use reqwest::Client;
+use actix_web::http::StatusCode;
+use actix_web::middleware::from_fn;
+use actix_web::web::{Data, JsonConfig, PathConfig, QueryConfig};
+use actix_web::{error, App, HttpServer, ResponseError};
+use sqlx::migrate::MigrateError;
 use sqlx::PgPool;
 use tokio::sync::Mutex;
 use tracing_actix_web::TracingLogger;
 
+use crate::configuration::client::ClientSettings;
 use crate::configuration::common::CommonSettings;
 use crate::configuration::itools::ItoolsSettings;
+use crate::configuration::limits::LimitSettings;
+use crate::configuration::postgres::PostgresSettings;
+use crate::configuration::qdrant::QdrantSettings;
 use crate::configuration::setting::Settings;
+use crate::dto::response::document::generally::DocumentResponse;
+use crate::middleware::request::request_context;
 use crate::route::document::register_document_route;
+use crate::route::itools::register_itools_route;
+use crate::worker::ingestion::{IngestionContext, IngestionPool, IngestionQueue};
 
 pub struct Application {
     server: Server,
     port: u16,
+    pool: IngestionPool,
 }
 
 impl Application {
     pub async fn build(configuration: Settings) -> Result<Self, std::io::Error> {
+        // 配置项之间的约束不满足时拒绝启动, 例如批量上传的文档数量超过入库队列的容量时批量上传永远无法成功
+        let problems = configuration.problems();
+        if !problems.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("配置校验未通过: {}", problems.join("; ")),
+            ));
+        }
+
         let addr = format!(
             "{}:{}",
             configuration.application.host, configuration.application.port
@@ -29,77 +49,151 @@ impl Application {
         let listener = TcpListener::bind(addr)?;
         let port = listener.local_addr()?.port();
 
-        let pgpool = configuration.postgres.get_postgres_connection_pool();
-
-        let qdrant = configuration
-            .qdrant
-            .get_qdrant_client()
-            .expect("Failed to build qdrant client");
+        let worker = &configuration.worker;
+        let context = build_context(
+            &configuration.postgres,
+            &configuration.qdrant,
+            &configuration.client,
+            configuration.itools,
+            configuration.common,
+        );
 
-        let client = configuration
-            .client
-            .get_proxy_client()
-            .expect("Failed to build proxy client");
+        // 启动时执行数据库迁移, 保证表结构与代码版本一致
+        migrate_database(&context.pgpool)
+            .await
+            .expect("Failed to migrate database");
 
         // 对qdrant客户端健康状况进行检查, 如果有异常就直接退出应用程序
         // qdrant.health_check().await.expect("向量数据库健康检查异常");
 
-        let itools = configuration.itools;
-
-        let common = configuration.common;
+        // 入库任务由固定数量的工作任务处理, 上传接口只负责排队
+        let (queue, pool) = IngestionPool::spawn(worker, context.clone());
 
-        let server = run(listener, pgpool, qdrant, client, itools, common)?;
+        let server = run(listener, context, queue)?;
 
-        Ok(Self { server, port })
+        Ok(Self { server, port, pool })
     }
 
     pub fn port(&self) -> u16 {
         self.port
     }
 
+    // 服务停止接收请求后, 等待已经排队的入库任务处理完成再退出
     pub async fn run_until_stopped(self) -> Result<(), std::io::Error> {
-        self.server.await
+        let result = self.server.await;
+        self.pool.shutdown().await;
+        result
+    }
+}
+
+// 服务和命令行共用的依赖, 数据库连接池只在首次使用时才建立连接
+pub fn build_context(
+    postgres: &PostgresSettings,
+    qdrant: &QdrantSettings,
+    client: &ClientSettings,
+    itools: ItoolsSettings,
+    common: CommonSettings,
+) -> IngestionContext {
+    let pgpool = postgres.get_postgres_connection_pool();
+
+    let qdrant = qdrant
+        .get_qdrant_client()
+        .expect("Failed to build qdrant client");
+
+    let client = client
+        .get_proxy_client()
+        .expect("Failed to build proxy client");
+
+    IngestionContext {
+        pgpool: Data::new(pgpool),
+        qdrant: Data::new(Arc::new(Mutex::new(qdrant))),
+        itools: Data::new(itools),
+        common: Data::new(common),
+        client: Data::new(client),
     }
 }
 
+pub async fn migrate_database(pgpool: &PgPool) -> Result<(), MigrateError> {
+    sqlx::migrate!("./migrations").run(pgpool).await
+}
+
 // 设置请求JSON的最大值为10M
-const MAX_JSON_BYTES: usize = 10 * 1 << 20;
+const MAX_JSON_BYTES: usize = 10 << 20;
+
+// 请求参数解析失败时同样返回统一格式的响应, 而不是actix-web默认的纯文本
+fn bad_request<E: ResponseError + 'static>(err: E) -> error::Error {
+    let response = DocumentResponse::failure(StatusCode::BAD_REQUEST, err.to_string());
+    error::InternalError::from_response(err, response).into()
+}
 
 fn build_json_configuration() -> JsonConfig {
     JsonConfig::default()
         .limit(MAX_JSON_BYTES)
-        .error_handler(|err, _req| {
-            error::InternalError::from_response(err, HttpResponse::Conflict().finish()).into()
-        })
+        .error_handler(|err, _req| bad_request(err))
+}
+
+fn build_query_configuration() -> QueryConfig {
+    QueryConfig::default().error_handler(|err, _req| bad_request(err))
+}
+
+fn build_path_configuration() -> PathConfig {
+    PathConfig::default().error_handler(|err, _req| bad_request(err))
+}
+
+// 上传请求的大小上限由配置决定, 超过上限时不再接收剩余内容, 各类型文件的大小在解析后单独检查
+fn build_multipart_configuration(limits: &LimitSettings) -> MultipartFormConfig {
+    MultipartFormConfig::default()
+        .total_limit(limits.request_size())
+        .error_handler(|err, _req| bad_request(err))
+}
+
+fn build_batch_multipart_configuration(limits: &LimitSettings) -> MultipartFormConfig {
+    MultipartFormConfig::default()
+        .total_limit(limits.batch_request_size())
+        .error_handler(|err, _req| bad_request(err))
 }
 
 pub fn run(
     listener: TcpListener,
-    pgpool: PgPool,
-    qdrant: Qdrant,
-    client: Client,
-    itools: ItoolsSettings,
-    common: CommonSettings,
+    context: IngestionContext,
+    queue: IngestionQueue,
 ) -> Result<Server, std::io::Error> {
     let server = HttpServer::new({
-        let client = Data::new(client);
-        let common = Data::new(common);
-        let itools = Data::new(itools);
-        let pgpool = Data::new(pgpool);
-        let qdrant = Data::new(Arc::new(Mutex::new(qdrant)));
+        let IngestionContext {
+            pgpool,
+            qdrant,
+            itools,
+            common,
+            client,
+        } = context;
+        let queue = Data::new(queue);
 
         move || {
             let json_configuration = build_json_configuration();
+            let query_configuration = build_query_configuration();
+            let path_configuration = build_path_configuration();
+            let multipart_configuration = build_multipart_configuration(&common.limits);
+            let batch_multipart_configuration = build_batch_multipart_configuration(&common.limits);
 
+            // 后注册的中间件先执行, TracingLogger需要先生成请求编号
             App::new()
+                .wrap(from_fn(request_context))
                 .wrap(TracingLogger::default())
                 .app_data(pgpool.clone())
                 .app_data(qdrant.clone())
                 .app_data(itools.clone())
                 .app_data(common.clone())
                 .app_data(client.clone())
+                .app_data(queue.clone())
                 .app_data(json_configuration)
-                .service(register_document_route())
+                .app_data(query_configuration)
+                .app_data(path_configuration)
+                .app_data(multipart_configuration)
+                .service(register_document_route(batch_multipart_configuration))
+                .service(register_itools_route())
+                .default_service(actix_web::web::to(|| async {
+                    DocumentResponse::failure(StatusCode::NOT_FOUND, "接口不存在")
+                }))
         }
     })
     .listen(listener)?
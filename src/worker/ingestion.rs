@@ -0,0 +1,317 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::web::Data;
+use anyhow::{anyhow, Context};
+use qdrant_client::Qdrant;
+use reqwest::Client;
+use sqlx::PgPool;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{self, Permit, PermitIterator, Receiver, Sender};
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{Instrument, Span};
+use uuid::Uuid;
+
+use crate::blunder::document::{DocumentError, ParseError};
+use crate::configuration::common::CommonSettings;
+use crate::configuration::itools::ItoolsSettings;
+use crate::configuration::worker::WorkerSettings;
+use crate::database::ingestion::{self, JobKind, JobRecord};
+use crate::service::document;
+
+// 排队等待处理的入库任务, 任务内容保存在数据库中, 队列中只传递任务主键,
+// 同时携带上传请求的跨度, 使后台处理的日志能够关联到原始请求
+pub struct IngestionTask {
+    id: Uuid,
+    span: Span,
+}
+
+impl IngestionTask {
+    pub fn new(id: Uuid) -> Self {
+        Self {
+            id,
+            span: Span::current(),
+        }
+    }
+}
+
+// 入库任务处理过程中需要的共享资源
+#[derive(Clone)]
+pub struct IngestionContext {
+    pub pgpool: Data<PgPool>,
+    pub qdrant: Data<Arc<Mutex<Qdrant>>>,
+    pub itools: Data<ItoolsSettings>,
+    pub common: Data<CommonSettings>,
+    pub client: Data<Client>,
+}
+
+// 入库队列的发送端, 作为应用数据注册到每个工作线程
+#[derive(Clone)]
+pub struct IngestionQueue {
+    sender: Sender<IngestionTask>,
+}
+
+impl IngestionQueue {
+    // 先占用队列中的位置再登记入库任务, 队列已满时不会留下无法处理的任务记录
+    pub fn reserve(&self) -> Result<Permit<'_, IngestionTask>, DocumentError> {
+        self.sender.try_reserve().map_err(unavailable)
+    }
+
+    // 批量上传时一次占用全部位置, 队列剩余位置不足时整批拒绝,
+    // 文档数量超过队列容量时无论何时重试都无法登记, 作为校验错误返回
+    pub fn reserve_many(
+        &self,
+        n: usize,
+    ) -> Result<PermitIterator<'_, IngestionTask>, DocumentError> {
+        let capacity = self.sender.max_capacity();
+        if n > capacity {
+            return Err(ParseError::TooManyFiles(capacity).into());
+        }
+        self.sender.try_reserve_many(n).map_err(unavailable)
+    }
+}
+
+fn unavailable(error: TrySendError<()>) -> DocumentError {
+    match error {
+        TrySendError::Full(_) => DocumentError::UnavailableError("入库队列已满, 请稍后重试".into()),
+        TrySendError::Closed(_) => {
+            DocumentError::UnavailableError("服务正在停止, 暂不接收新的文档".into())
+        }
+    }
+}
+
+// 入库任务的工作线程池, 启动时重新排队上次没有完成的任务并定期回收租约过期的任务,
+// 停机时停止接收新任务并等待队列中的任务处理完成
+pub struct IngestionPool {
+    shutdown: watch::Sender<bool>,
+    workers: Vec<JoinHandle<()>>,
+    timeout: Duration,
+}
+
+impl IngestionPool {
+    pub fn spawn(settings: &WorkerSettings, context: IngestionContext) -> (IngestionQueue, Self) {
+        let (sender, receiver) = mpsc::channel(settings.queue_depth.max(1));
+        let (shutdown, _) = watch::channel(false);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let lease = settings.lease_timeout;
+
+        tokio::spawn(recover(
+            sender.clone(),
+            context.pgpool.clone(),
+            lease,
+            shutdown.subscribe(),
+        ));
+
+        let workers = (0..settings.concurrency.max(1))
+            .map(|index| {
+                tokio::spawn(worker(
+                    index,
+                    lease,
+                    receiver.clone(),
+                    shutdown.subscribe(),
+                    context.clone(),
+                ))
+            })
+            .collect();
+
+        let pool = Self {
+            shutdown,
+            workers,
+            timeout: Duration::from_secs(settings.drain_timeout),
+        };
+        (IngestionQueue { sender }, pool)
+    }
+
+    #[tracing::instrument(name = "Drain ingestion workers", skip(self))]
+    pub async fn shutdown(self) {
+        self.shutdown.send_replace(true);
+        let workers = futures::future::join_all(self.workers);
+        if tokio::time::timeout(self.timeout, workers).await.is_err() {
+            tracing::warn!("Ingestion workers did not finish before drain timeout");
+        }
+    }
+}
+
+// 服务启动时重新排队尚未完成的入库任务, 队列已满时等待工作任务腾出位置;
+// 之后每隔一个租约周期释放并重新排队租约过期的任务, 领取任务的进程在租约期内重启时,
+// 这些任务在租约过期后仍然能够继续处理
+#[tracing::instrument(
+    name = "Recover pending ingestion jobs",
+    skip(sender, pgpool, shutdown)
+)]
+async fn recover(
+    sender: Sender<IngestionTask>,
+    pgpool: Data<PgPool>,
+    lease: u64,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let jobs = match ingestion::select_pending_jobs(&pgpool, lease).await {
+        Ok(jobs) => jobs,
+        Err(error) => {
+            tracing::error!(error = ?error);
+            Vec::new()
+        }
+    };
+    tracing::info!(jobs = jobs.len(), "Resume pending ingestion jobs");
+    if !requeue(&sender, jobs).await {
+        return;
+    }
+
+    let period = Duration::from_secs(lease.max(1));
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(period) => {}
+            _ = shutdown.changed() => return,
+        }
+        let jobs = match ingestion::release_expired_jobs(&pgpool, lease).await {
+            Ok(jobs) => jobs,
+            Err(error) => {
+                tracing::error!(error = ?error);
+                continue;
+            }
+        };
+        if !jobs.is_empty() {
+            tracing::warn!(
+                jobs = jobs.len(),
+                "Requeue ingestion jobs with expired lease"
+            );
+        }
+        if !requeue(&sender, jobs).await {
+            return;
+        }
+    }
+}
+
+// 队列已经关闭时返回false
+async fn requeue(sender: &Sender<IngestionTask>, jobs: Vec<Uuid>) -> bool {
+    for id in jobs {
+        if sender.send(IngestionTask::new(id)).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+async fn worker(
+    index: usize,
+    lease: u64,
+    receiver: Arc<Mutex<Receiver<IngestionTask>>>,
+    mut shutdown: watch::Receiver<bool>,
+    context: IngestionContext,
+) {
+    loop {
+        let task = {
+            let mut receiver = receiver.lock().await;
+            tokio::select! {
+                task = receiver.recv() => task,
+                _ = shutdown.changed() => {
+                    // 关闭队列后不再接收新任务, 但已经排队的任务仍然可以取出
+                    receiver.close();
+                    receiver.recv().await
+                }
+            }
+        };
+        let Some(task) = task else {
+            tracing::info!(worker = index, "Ingestion worker stopped");
+            break;
+        };
+        let span = tracing::info_span!(parent: &task.span, "Ingestion task", worker = index, job = %task.id);
+        process(task.id, lease, &context).instrument(span).await;
+    }
+}
+
+// 命令行重新入库时也通过这里处理任务, 与工作任务的领取和错误记录保持一致
+pub async fn process(id: Uuid, lease: u64, context: &IngestionContext) {
+    // 任务可能已经被其他工作任务领取, 或者在排队期间已经完成
+    let record = match ingestion::claim_job(&context.pgpool, id, lease).await {
+        Ok(Some(record)) => record,
+        Ok(None) => {
+            tracing::info!("Ingestion job is claimed or finished, skip");
+            return;
+        }
+        Err(error) => {
+            tracing::error!(error = ?error);
+            return;
+        }
+    };
+    if let Err(error) = ingest(record, context).await {
+        // error = %error, 只会记录最顶层错误
+        // error = ?error, 会记录完整的错误链
+        tracing::error!(error = ?error);
+        // 记录完整的错误链和失败类型, 便于运维人员排查失败原因
+        let kind = error.kind();
+        let error = format!("{:?}", error);
+        if let Err(error) = ingestion::update_error(&context.pgpool, id, kind, &error).await {
+            tracing::error!(error = ?error);
+        }
+    }
+}
+
+async fn ingest(record: JobRecord, context: &IngestionContext) -> Result<(), DocumentError> {
+    let IngestionContext {
+        pgpool,
+        qdrant,
+        itools,
+        common,
+        client,
+    } = context;
+    let job = record.handle()?;
+    let kind = JobKind::try_from(record.kind.as_str()).map_err(|error| anyhow!(error))?;
+    let metadata = record
+        .metadata
+        .with_context(|| format!("Missing metadata of ingestion job {}", record.id))?;
+    match kind {
+        JobKind::Thinktank => {
+            let domain = serde_json::from_value(metadata)
+                .with_context(|| format!("Failed to parse metadata of ingestion job {}", job.id))?;
+            document::thinktank::ingest(job, domain, pgpool, qdrant, itools, common, client).await
+        }
+        JobKind::Guideline => {
+            let domain = serde_json::from_value(metadata)
+                .with_context(|| format!("Failed to parse metadata of ingestion job {}", job.id))?;
+            document::guideline::ingest(job, domain, pgpool, qdrant, itools, common, client).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_when_queue_is_full() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let queue = IngestionQueue { sender };
+
+        let permit = queue.reserve().expect("queue should have capacity");
+        assert!(matches!(
+            queue.reserve(),
+            Err(DocumentError::UnavailableError(_))
+        ));
+        drop(permit);
+
+        receiver.close();
+        assert!(matches!(
+            queue.reserve(),
+            Err(DocumentError::UnavailableError(_))
+        ));
+    }
+
+    #[test]
+    fn reject_batch_larger_than_queue() {
+        let (sender, _receiver) = mpsc::channel(2);
+        let queue = IngestionQueue { sender };
+
+        assert!(matches!(
+            queue.reserve_many(3),
+            Err(DocumentError::ValidationError(ParseError::TooManyFiles(2)))
+        ));
+        let permits = queue.reserve_many(2).expect("queue should have capacity");
+        assert!(matches!(
+            queue.reserve_many(1),
+            Err(DocumentError::UnavailableError(_))
+        ));
+        drop(permits);
+    }
+}